@@ -0,0 +1,35 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Embeds the bundled app definitions so that an installed binary works
+// without the `apps` directory next to it.
+fn main() {
+    println!("cargo:rerun-if-changed=apps");
+
+    let apps_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("apps");
+    let mut files = fs::read_dir(&apps_dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "vacuum" || ext == "inc")
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut code = String::from("pub static EMBEDDED_APPS: &[(&str, &str)] = &[\n");
+    for file in files {
+        let file_name = file.file_name().unwrap().to_str().unwrap();
+        code.push_str(&format!(
+            "    ({:?}, include_str!({:?})),\n",
+            file_name,
+            file.display().to_string()
+        ));
+    }
+    code.push_str("];\n");
+
+    let out_file = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded_apps.rs");
+    fs::write(out_file, code).unwrap();
+}
@@ -1,4 +1,6 @@
+use crate::adapters::context::stored_path;
 use crate::application::context::Context;
+use crate::domain::KnownFolder;
 use std::path::PathBuf;
 
 #[derive(Clone)]
@@ -19,34 +21,32 @@ impl Context for TargetDirectoryContext {
         self.target.clone()
     }
 
-    fn home(&self) -> Self {
-        Self {
-            target: self.target.sub("home"),
-        }
-    }
-
-    fn config(&self) -> Self {
-        Self {
-            target: self.target.sub("config"),
-        }
+    fn known(&self, folder: KnownFolder) -> Option<Self> {
+        Some(Self {
+            target: self.target.sub(folder.name()),
+        })
     }
 
-    fn local(&self) -> Self {
+    fn root<S: AsRef<str>>(&self, path: S) -> Self {
         Self {
-            target: self.target.sub("local"),
+            target: self.target.sub("root").sub(stored_path(path.as_ref())),
         }
     }
 
     fn sub<S: AsRef<str>>(&self, sub: S) -> Self {
         let sub = sub.as_ref();
-        let target = self.target.sub(sub);
+        let target = self.target.sub(stored_path(sub));
 
         Self { target }
     }
 
-    fn search(&self, pattern: &str) -> Vec<Self> {
+    fn sub_as<S: AsRef<str>>(&self, _: S, stored: S) -> Self {
+        self.sub(stored)
+    }
+
+    fn search(&self, pattern: &str, exclude: &[String]) -> Vec<Self> {
         let mut ret = Vec::new();
-        let sources = self.target.search(pattern);
+        let sources = self.target.search(pattern, exclude);
         for source in sources {
             let remaining = source.strip_prefix(self.target.as_path()).unwrap();
             let target = self.target.sub(remaining.to_str().unwrap());
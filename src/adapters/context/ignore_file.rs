@@ -0,0 +1,57 @@
+use crate::adapters::executor::METADATA_FILE;
+use crate::adapters::use_cases::MANIFEST_FILE;
+use crate::application::error::VacuumError;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Name of the ignore file read from the root of the store folder.
+pub const IGNORE_FILE: &str = ".vacuumignore";
+
+/// Paths of the store folder listed in its `.vacuumignore` file, never stored or restored.
+pub struct IgnoreFile {
+    rules: Option<Gitignore>,
+}
+
+impl IgnoreFile {
+    /// Reads the ignore file of `store_folder`, a missing one ignores nothing.
+    pub fn load(store_folder: &Path) -> Result<Self, VacuumError> {
+        let path = store_folder.join(IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(Self { rules: None });
+        }
+
+        let mut builder = GitignoreBuilder::new(store_folder);
+        if let Some(e) = builder.add(&path) {
+            return Err(invalid(&path, e));
+        }
+        let rules = builder.build().map_err(|e| invalid(&path, e))?;
+        Ok(Self { rules: Some(rules) })
+    }
+
+    /// Reads the ignore file of the store folder the `app_dir` is in.
+    pub fn for_app(app_dir: &Path) -> Result<Rc<Self>, VacuumError> {
+        let store_folder = app_dir.parent().unwrap_or(app_dir);
+        Ok(Rc::new(Self::load(store_folder)?))
+    }
+
+    /// Whether `stored` is ignored, the metadata and manifest files always are.
+    pub fn is_ignored(&self, stored: &Path, is_dir: bool) -> bool {
+        if stored
+            .file_name()
+            .is_some_and(|name| name == METADATA_FILE || name == MANIFEST_FILE)
+        {
+            return true;
+        }
+        match &self.rules {
+            Some(rules) if stored.starts_with(rules.path()) => rules
+                .matched_path_or_any_parents(stored, is_dir)
+                .is_ignore(),
+            _ => false,
+        }
+    }
+}
+
+fn invalid(path: &Path, e: ignore::Error) -> VacuumError {
+    VacuumError::InvalidPattern(path.display().to_string(), e.to_string())
+}
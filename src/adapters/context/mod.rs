@@ -1,4 +1,17 @@
+use std::path::{Component, Path, PathBuf};
+
 pub mod deps_context;
+pub mod ignore_file;
 pub mod pathbuf_context;
 pub mod restore_context;
 pub mod store_context;
+
+/// The part of `path` that can be joined onto the store folder.
+pub(crate) fn stored_path(path: &str) -> String {
+    Path::new(path)
+        .components()
+        .filter(|c| !matches!(c, Component::Prefix(_) | Component::RootDir))
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .into_owned()
+}
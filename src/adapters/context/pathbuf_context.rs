@@ -1,4 +1,6 @@
+use crate::adapters::tree;
 use crate::application::context::Context;
+use crate::domain::KnownFolder;
 use std::path::PathBuf;
 
 impl Context for PathBuf {
@@ -6,15 +8,24 @@ impl Context for PathBuf {
     fn current(&self) -> Self::Current {
         self.clone()
     }
-    fn home(&self) -> Self {
-        dirs_next::home_dir().unwrap()
-    }
-    fn config(&self) -> Self {
-        dirs_next::config_dir().unwrap()
+    fn known(&self, folder: KnownFolder) -> Option<Self> {
+        match folder {
+            KnownFolder::Home => dirs_next::home_dir(),
+            KnownFolder::Config => dirs_next::config_dir(),
+            KnownFolder::Local => dirs_next::data_local_dir(),
+            KnownFolder::Data => dirs_next::data_dir(),
+            KnownFolder::Cache => dirs_next::cache_dir(),
+            KnownFolder::State => state_dir(),
+            KnownFolder::Runtime => dirs_next::runtime_dir(),
+            KnownFolder::Bin => dirs_next::executable_dir(),
+            KnownFolder::Fonts => dirs_next::font_dir(),
+            KnownFolder::Desktop => dirs_next::desktop_dir(),
+            KnownFolder::Documents => dirs_next::document_dir(),
+        }
     }
 
-    fn local(&self) -> Self {
-        dirs_next::data_local_dir().unwrap()
+    fn root<S: AsRef<str>>(&self, path: S) -> Self {
+        PathBuf::from(path.as_ref())
     }
 
     fn sub<S: AsRef<str>>(&self, sub: S) -> Self {
@@ -23,7 +34,13 @@ impl Context for PathBuf {
         s
     }
 
-    fn search(&self, pattern: &str) -> Vec<Self> {
+    fn sub_as<S: AsRef<str>>(&self, live: S, _: S) -> Self {
+        self.sub(live)
+    }
+
+    fn search(&self, pattern: &str, exclude: &[String]) -> Vec<Self> {
+        // invalid patterns are rejected when they are expanded
+        let exclude = tree::patterns(exclude).unwrap_or_default();
         let mut ret = vec![];
         let full_pattern = format!(
             "{}{}{}",
@@ -31,11 +48,27 @@ impl Context for PathBuf {
             std::path::MAIN_SEPARATOR,
             pattern
         );
-        for entry in glob::glob(full_pattern.as_ref()).unwrap() {
-            if let Ok(path) = entry {
-                ret.push(path);
+        for path in glob::glob(full_pattern.as_ref())
+            .into_iter()
+            .flatten()
+            .flatten()
+        {
+            if tree::excluded(&exclude, path.strip_prefix(self).unwrap()) {
+                continue;
             }
+            ret.push(path);
         }
         ret
     }
 }
+
+/// `$XDG_STATE_HOME` or `~/.local/state`, only defined on Linux.
+fn state_dir() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    match std::env::var_os("XDG_STATE_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => dirs_next::home_dir().map(|home| home.join(".local").join("state")),
+    }
+}
@@ -1,17 +1,23 @@
-use crate::application::context::Context;
-use std::path::PathBuf;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::stored_path;
+use crate::application::context::{Context, Direction, PairedContext};
+use crate::domain::KnownFolder;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct RestoreContext {
     pub source: PathBuf,
     pub target: PathBuf,
+    pub ignore: Rc<IgnoreFile>,
 }
 
 impl RestoreContext {
-    pub fn new(source_dir: PathBuf) -> Self {
+    pub fn new(source_dir: PathBuf, ignore: Rc<IgnoreFile>) -> Self {
         Self {
             source: source_dir,
             target: PathBuf::default(),
+            ignore,
         }
     }
 }
@@ -25,43 +31,76 @@ impl Context for RestoreContext {
         (s, t)
     }
 
-    fn home(&self) -> Self {
-        Self {
-            source: self.source.sub("home"),
-            target: self.target.home(),
-        }
-    }
-
-    fn config(&self) -> Self {
-        Self {
-            source: self.source.sub("config"),
-            target: self.target.config(),
-        }
+    fn known(&self, folder: KnownFolder) -> Option<Self> {
+        Some(Self {
+            source: self.source.sub(folder.name()),
+            target: self.target.known(folder)?,
+            ignore: self.ignore.clone(),
+        })
     }
 
-    fn local(&self) -> Self {
+    fn root<S: AsRef<str>>(&self, path: S) -> Self {
+        let path = path.as_ref();
         Self {
-            source: self.source.sub("local"),
-            target: self.target.local(),
+            source: self.source.sub("root").sub(stored_path(path)),
+            target: self.target.root(path),
+            ignore: self.ignore.clone(),
         }
     }
 
     fn sub<S: AsRef<str>>(&self, sub: S) -> Self {
         let sub = sub.as_ref();
-        let source = self.source.sub(sub);
+        let source = self.source.sub(stored_path(sub));
         let target = self.target.sub(sub);
 
-        Self { source, target }
+        Self {
+            source,
+            target,
+            ignore: self.ignore.clone(),
+        }
     }
 
-    fn search(&self, pattern: &str) -> Vec<Self> {
+    fn sub_as<S: AsRef<str>>(&self, live: S, stored: S) -> Self {
+        Self {
+            source: self.source.sub(stored_path(stored.as_ref())),
+            target: self.target.sub(live),
+            ignore: self.ignore.clone(),
+        }
+    }
+
+    fn search(&self, pattern: &str, exclude: &[String]) -> Vec<Self> {
         let mut ret = Vec::new();
-        let sources = self.source.search(pattern);
+        let sources = self.source.search(pattern, exclude);
         for source in sources {
             let remaining = source.strip_prefix(self.source.as_path()).unwrap();
             let target = self.target.sub(remaining.to_str().unwrap());
-            ret.push(Self { source, target })
+            if self.ignore.is_ignored(&source, source.is_dir()) {
+                continue;
+            }
+            ret.push(Self {
+                source,
+                target,
+                ignore: self.ignore.clone(),
+            })
         }
         ret
     }
 }
+
+impl PairedContext for RestoreContext {
+    fn direction(&self) -> Direction {
+        Direction::Restore
+    }
+
+    fn live(&self) -> PathBuf {
+        self.target.clone()
+    }
+
+    fn stored(&self) -> PathBuf {
+        self.source.clone()
+    }
+
+    fn is_ignored(&self, stored: &Path, is_dir: bool) -> bool {
+        self.ignore.is_ignored(stored, is_dir)
+    }
+}
@@ -1,17 +1,23 @@
-use crate::application::context::Context;
-use std::path::PathBuf;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::stored_path;
+use crate::application::context::{Context, Direction, PairedContext};
+use crate::domain::KnownFolder;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct StoreContext {
     pub source: PathBuf,
     pub target: PathBuf,
+    pub ignore: Rc<IgnoreFile>,
 }
 
 impl StoreContext {
-    pub fn new(target_dir: PathBuf) -> Self {
+    pub fn new(target_dir: PathBuf, ignore: Rc<IgnoreFile>) -> Self {
         Self {
             source: PathBuf::default(),
             target: target_dir,
+            ignore,
         }
     }
 }
@@ -25,43 +31,76 @@ impl Context for StoreContext {
         (s, t)
     }
 
-    fn home(&self) -> Self {
-        Self {
-            source: self.source.home(),
-            target: self.target.sub("home"),
-        }
-    }
-
-    fn config(&self) -> Self {
-        Self {
-            source: self.source.config(),
-            target: self.target.sub("config"),
-        }
+    fn known(&self, folder: KnownFolder) -> Option<Self> {
+        Some(Self {
+            source: self.source.known(folder)?,
+            target: self.target.sub(folder.name()),
+            ignore: self.ignore.clone(),
+        })
     }
 
-    fn local(&self) -> Self {
+    fn root<S: AsRef<str>>(&self, path: S) -> Self {
+        let path = path.as_ref();
         Self {
-            source: self.source.local(),
-            target: self.target.sub("local"),
+            source: self.source.root(path),
+            target: self.target.sub("root").sub(stored_path(path)),
+            ignore: self.ignore.clone(),
         }
     }
 
     fn sub<S: AsRef<str>>(&self, sub: S) -> Self {
         let sub = sub.as_ref();
         let source = self.source.sub(sub);
-        let target = self.target.sub(sub);
+        let target = self.target.sub(stored_path(sub));
 
-        Self { source, target }
+        Self {
+            source,
+            target,
+            ignore: self.ignore.clone(),
+        }
     }
 
-    fn search(&self, pattern: &str) -> Vec<Self> {
+    fn sub_as<S: AsRef<str>>(&self, live: S, stored: S) -> Self {
+        Self {
+            source: self.source.sub(live),
+            target: self.target.sub(stored_path(stored.as_ref())),
+            ignore: self.ignore.clone(),
+        }
+    }
+
+    fn search(&self, pattern: &str, exclude: &[String]) -> Vec<Self> {
         let mut ret = Vec::new();
-        let sources = self.source.search(pattern);
+        let sources = self.source.search(pattern, exclude);
         for source in sources {
             let remaining = source.strip_prefix(self.source.as_path()).unwrap();
             let target = self.target.sub(remaining.to_str().unwrap());
-            ret.push(Self { source, target })
+            if self.ignore.is_ignored(&target, source.is_dir()) {
+                continue;
+            }
+            ret.push(Self {
+                source,
+                target,
+                ignore: self.ignore.clone(),
+            })
         }
         ret
     }
 }
+
+impl PairedContext for StoreContext {
+    fn direction(&self) -> Direction {
+        Direction::Store
+    }
+
+    fn live(&self) -> PathBuf {
+        self.source.clone()
+    }
+
+    fn stored(&self) -> PathBuf {
+        self.target.clone()
+    }
+
+    fn is_ignored(&self, stored: &Path, is_dir: bool) -> bool {
+        self.ignore.is_ignored(stored, is_dir)
+    }
+}
@@ -0,0 +1,34 @@
+use crate::application::error::VacuumError;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+include!(concat!(env!("OUT_DIR"), "/embedded_apps.rs"));
+
+/// Writes the embedded app definitions into `dir`, replacing existing files only with `force`.
+pub fn export(dir: &Path, force: bool) -> Result<Vec<PathBuf>, VacuumError> {
+    if !force {
+        if let Some(path) = EMBEDDED_APPS
+            .iter()
+            .map(|(file_name, _)| dir.join(file_name))
+            .find(|path| path.exists())
+        {
+            return Err(VacuumError::IoError(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists, use --force to overwrite",
+                    path.display()
+                ),
+            )));
+        }
+    }
+
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (file_name, content) in EMBEDDED_APPS {
+        let path = dir.join(file_name);
+        fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,233 @@
+use super::logger::Logger;
+use super::process;
+use crate::adapters::tree::{self, EntryKind};
+use crate::application::context::{Direction, PairedContext};
+use crate::application::error::VacuumError;
+use crate::application::handler::Handler;
+use crate::application::report::Report;
+use crate::domain::{Command, CommandMode, DependencyCheck, Directory};
+use colored::*;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// A `Handler` that only reports what `FileSystemExecutor` would do.
+pub struct DryRunExecutor<C> {
+    logger: Logger,
+    created_dirs: RefCell<HashSet<PathBuf>>,
+    link: bool,
+    report: RefCell<Report>,
+    written: RefCell<BTreeSet<PathBuf>>,
+    _phantom: PhantomData<C>,
+}
+
+impl<C> DryRunExecutor<C> {
+    pub fn new(name: String) -> Self {
+        Self {
+            logger: Logger::new(name),
+            created_dirs: RefCell::new(HashSet::new()),
+            link: false,
+            report: RefCell::new(Report::default()),
+            written: RefCell::new(BTreeSet::new()),
+            _phantom: Default::default(),
+        }
+    }
+
+    /// Plans links to the sources instead of copies.
+    pub fn link(mut self, link: bool) -> Self {
+        self.link = link;
+        self
+    }
+
+    pub fn into_report(self) -> Report {
+        self.report.into_inner()
+    }
+
+    /// Every target that would be written so far.
+    pub fn written(&self) -> BTreeSet<PathBuf> {
+        self.written.borrow().clone()
+    }
+
+    fn plan_create_dir(&self, dir: &Path) {
+        if dir.exists() || !self.created_dirs.borrow_mut().insert(dir.to_path_buf()) {
+            return;
+        }
+        self.logger
+            .print(format!("{} {}", "Create".yellow(), dir.display()));
+    }
+
+    fn plan_copy(&self, source: &Path, target: &Path) {
+        if self.link {
+            return self.plan_link(source, target);
+        }
+        if let Some(parent) = target.parent() {
+            self.plan_create_dir(parent);
+        }
+        self.written.borrow_mut().insert(target.to_path_buf());
+        self.report.borrow_mut().copied += 1;
+        self.logger.print(format!(
+            "{} {} -> {}",
+            "Copy".blue(),
+            source.display(),
+            target.display()
+        ));
+    }
+
+    fn plan_link(&self, original: &Path, target: &Path) {
+        if let Some(parent) = target.parent() {
+            self.plan_create_dir(parent);
+        }
+        self.written.borrow_mut().insert(target.to_path_buf());
+        self.report.borrow_mut().copied += 1;
+        self.logger.print(format!(
+            "{} {} -> {}",
+            "Link".blue(),
+            target.display(),
+            original.display()
+        ));
+    }
+
+    fn plan_execute(
+        &self,
+        ctx: &C,
+        command: &Command,
+        redirect: Option<String>,
+    ) -> Result<(), VacuumError>
+    where
+        C: PairedContext,
+    {
+        self.report.borrow_mut().commands += 1;
+        let args = process::command_line(command)?;
+        let mut line = match command.mode {
+            CommandMode::Args => format!("{} ({}) {:?}", "Execute".blue(), command.mode, args),
+            CommandMode::Shell => format!(
+                "{} ({}) {}",
+                "Execute".blue(),
+                command.mode,
+                command.command
+            ),
+        };
+        if let Some(redirect) = redirect {
+            line.push_str(&redirect);
+        }
+        line.push_str(&format!(" in {}", ctx.live().display()));
+        let mut details = Vec::new();
+        if let Some(timeout) = process::timeout(command) {
+            details.push(format!("timeout {}s", timeout.as_secs()));
+        }
+        for (key, value) in &command.env {
+            details.push(format!("{}={}", key, value));
+        }
+        if command.optional {
+            details.push("optional".to_owned());
+        }
+        if !details.is_empty() {
+            line.push_str(&format!(" ({})", details.join(", ")));
+        }
+        self.logger.print(line);
+        Ok(())
+    }
+}
+
+impl<C> Handler for DryRunExecutor<C>
+where
+    C: PairedContext,
+{
+    type Context = C;
+
+    fn handle_file(
+        &self,
+        ctx: &Self::Context,
+        _: &Option<Vec<DependencyCheck>>,
+    ) -> Result<(), VacuumError> {
+        let (source, target) = ctx.current();
+        if source.exists() {
+            self.plan_copy(&source, &target);
+        } else {
+            self.report.borrow_mut().skipped += 1;
+        }
+        Ok(())
+    }
+
+    fn handle_files<S: AsRef<str>>(
+        &self,
+        ctx: &Self::Context,
+        pattern: S,
+        exclude: &[String],
+    ) -> Result<(), VacuumError> {
+        for found in ctx.search(pattern.as_ref(), exclude) {
+            let (source, target) = found.current();
+            if source.is_dir() {
+                continue;
+            }
+            self.plan_copy(&source, &target);
+        }
+        Ok(())
+    }
+
+    fn handle_dir(&self, ctx: &Self::Context, dir: &Directory) -> Result<(), VacuumError> {
+        let ctx = ctx.sub(&dir.name);
+        let (source, target) = ctx.current();
+        if !source.is_dir() {
+            self.report.borrow_mut().skipped += 1;
+            return Ok(());
+        }
+
+        self.plan_create_dir(&target);
+        for entry in tree::walk(&source, dir.symlinks, &dir.exclude)? {
+            if ctx.is_ignored(
+                &ctx.stored().join(&entry.path),
+                entry.kind == EntryKind::Dir,
+            ) {
+                continue;
+            }
+            let (from, to) = (source.join(&entry.path), target.join(&entry.path));
+            match &entry.kind {
+                EntryKind::Dir => self.plan_create_dir(&to),
+                EntryKind::File => self.plan_copy(&from, &to),
+                EntryKind::Symlink(original) => self.plan_link(original, &to),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_execute(&self, ctx: &Self::Context, command: &Command) -> Result<(), VacuumError> {
+        if ctx.direction() == Direction::Store {
+            let output = command
+                .output
+                .as_ref()
+                .map(|file_name| ctx.stored().join(file_name));
+            let redirect = output
+                .as_ref()
+                .map(|output| format!(" >> {}", output.display()));
+            self.written.borrow_mut().extend(output);
+            return self.plan_execute(ctx, command, redirect);
+        }
+        if command.on_restore.is_none() {
+            return Ok(());
+        }
+
+        let (source, captured) = match &command.output {
+            Some(file_name) => {
+                let source = ctx.stored().join(file_name);
+                if !source.is_file() {
+                    self.report.borrow_mut().skipped += 1;
+                    return Ok(());
+                }
+                let captured = fs::read_to_string(&source)?;
+                (Some(source), Some(captured))
+            }
+            None => (None, None),
+        };
+        for (restore, input) in process::restore_commands(command, captured.as_deref()) {
+            let redirect = match (&source, input) {
+                (Some(source), Some(_)) => Some(format!(" < {}", source.display())),
+                _ => None,
+            };
+            self.plan_execute(ctx, &restore, redirect)?;
+        }
+        Ok(())
+    }
+}
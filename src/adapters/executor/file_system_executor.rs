@@ -1,111 +1,466 @@
+use super::journal::Journal;
 use super::logger::Logger;
-use crate::application::context::Context;
+use super::metadata::{self, FileMetadata, Metadata};
+use super::process;
+use crate::adapters::tree::{self, EntryKind};
+use crate::application::context::{Direction, PairedContext};
 use crate::application::error::VacuumError;
 use crate::application::handler::Handler;
-use crate::domain::DependencyCheck;
+use crate::application::report::Report;
+use crate::domain::{Command, DependencyCheck, Directory};
 use colored::*;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fs;
 use std::marker::PhantomData;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
-#[derive(Clone)]
 pub struct FileSystemExecutor<C> {
     logger: Logger,
+    journal: Option<Rc<Journal>>,
+    metadata: Option<Rc<Metadata>>,
+    keep_going: bool,
+    link: bool,
+    force: bool,
+    report: RefCell<Report>,
+    written: RefCell<BTreeSet<PathBuf>>,
     _phantom: PhantomData<C>,
 }
 
-impl<C> FileSystemExecutor<C> {
+impl<C> FileSystemExecutor<C>
+where
+    C: PairedContext,
+{
     pub fn new(name: String) -> Self {
         Self {
             logger: Logger::new(name),
+            journal: None,
+            metadata: None,
+            keep_going: false,
+            link: false,
+            force: false,
+            report: RefCell::new(Report::default()),
+            written: RefCell::new(BTreeSet::new()),
             _phantom: Default::default(),
         }
     }
+
+    /// Backs up every file about to be overwritten into `journal`.
+    pub fn with_journal(mut self, journal: Rc<Journal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Records file modes, times and link targets in `metadata` and reapplies them.
+    pub fn with_metadata(mut self, metadata: Rc<Metadata>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Collects failures into the report instead of stopping at the first one.
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Links every target to its source, replacing existing files only with `force`.
+    pub fn link(mut self, link: bool, force: bool) -> Self {
+        self.link = link;
+        self.force = force;
+        self
+    }
+
+    pub fn into_report(self) -> Report {
+        self.report.into_inner()
+    }
+
+    /// Every target written so far, including links already in place.
+    pub fn written(&self) -> BTreeSet<PathBuf> {
+        self.written.borrow().clone()
+    }
+
+    fn attempt<S: AsRef<str>>(
+        &self,
+        what: S,
+        result: Result<(), VacuumError>,
+    ) -> Result<(), VacuumError> {
+        match result {
+            Err(e) if self.keep_going => {
+                let what = what.as_ref().to_owned();
+                self.logger
+                    .print(format!("{} {}: {}", "Failed".red(), what, e));
+                self.report.borrow_mut().failures.push((what, e));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn backup(&self, target: &Path, source: Option<&Path>) -> Result<(), VacuumError> {
+        let journal = match &self.journal {
+            Some(journal) => journal,
+            None => return Ok(()),
+        };
+        if let Some(source) = source {
+            if same_content(source, target) {
+                return Ok(());
+            }
+        }
+        journal.record(target)
+    }
+
+    fn copy(&self, direction: Direction, source: &Path, target: &Path) -> Result<(), VacuumError> {
+        if self.link {
+            return self.link_to_source(source, target);
+        }
+        let recorded = match (&self.metadata, direction) {
+            (Some(metadata), Direction::Restore) => metadata.get(source),
+            _ => None,
+        };
+        if let (Some(metadata), Some(link)) = (
+            &self.metadata,
+            recorded.as_ref().and_then(|m| m.link.as_ref()),
+        ) {
+            let original = target
+                .parent()
+                .expect("Failed to get parent directory")
+                .join(link);
+            if original.exists() && !metadata.is_stored(&original) {
+                return self.replace_with_link(link, target);
+            }
+            if !original.exists() {
+                self.warn(format!(
+                    "{} pointed to missing {}, restoring a copy",
+                    target.display(),
+                    link.display()
+                ));
+            }
+        }
+
+        let linked = is_symlink(target);
+        // a live file linked by `restore --link` already is the stored file,
+        // copying a file onto itself would truncate it
+        if !linked && same_file(source, target) {
+            self.written.borrow_mut().insert(target.to_path_buf());
+            return Ok(());
+        }
+
+        let dest_dir = target.parent().expect("Failed to get parent directory");
+        fs::create_dir_all(dest_dir)?;
+        if linked {
+            self.backup(target, None)?;
+            if is_symlink(target) {
+                fs::remove_file(target)?;
+            }
+        } else {
+            self.backup(target, Some(source))?;
+        }
+        fs::copy(source, target)?;
+        self.written.borrow_mut().insert(target.to_path_buf());
+        self.report.borrow_mut().copied += 1;
+        self.logger
+            .print(format!("{} {}", "Copy".blue(), source.display()));
+
+        match (&self.metadata, direction) {
+            (Some(metadata), Direction::Store) => {
+                let mut live = FileMetadata::read(source)?;
+                // links made by `restore --link` are not part of the config
+                if metadata.is_stored(source) {
+                    live.link = None;
+                }
+                if !metadata::keeps_mode(target, &live) {
+                    self.warn(format!(
+                        "store folder cannot keep mode {:o} of {}",
+                        live.mode.unwrap_or_default(),
+                        source.display()
+                    ));
+                }
+                metadata.record(target, live);
+            }
+            (Some(_), Direction::Restore) => {
+                if let Some(Err(e)) = recorded.map(|m| m.apply(target)) {
+                    self.warn(format!(
+                        "cannot restore mode and time of {}: {}",
+                        target.display(),
+                        e
+                    ));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn warn<S: AsRef<str>>(&self, message: S) {
+        self.logger
+            .print(format!("{} {}", "Warning".yellow(), message.as_ref()));
+    }
+
+    fn link_to_source(&self, source: &Path, target: &Path) -> Result<(), VacuumError> {
+        if target.exists() && !is_symlink(target) && !self.force {
+            return Err(VacuumError::WouldReplace(target.to_path_buf()));
+        }
+        self.replace_with_link(source, target)
+    }
+
+    /// Points `target` at `original`, replacing whatever file or link is there.
+    fn replace_with_link(&self, original: &Path, target: &Path) -> Result<(), VacuumError> {
+        self.written.borrow_mut().insert(target.to_path_buf());
+        if fs::read_link(target).is_ok_and(|current| current == original) {
+            return Ok(());
+        }
+        fs::create_dir_all(target.parent().expect("Failed to get parent directory"))?;
+        self.backup(target, None)?;
+        if fs::symlink_metadata(target).is_ok() {
+            fs::remove_file(target)?;
+        }
+        tree::symlink(original, target)?;
+        self.report.borrow_mut().copied += 1;
+        self.logger.print(format!(
+            "{} {} -> {}",
+            "Link".blue(),
+            target.display(),
+            original.display()
+        ));
+        Ok(())
+    }
+
+    fn execute(&self, ctx: &C, command: &Command) -> Result<(), VacuumError> {
+        match ctx.direction() {
+            Direction::Store => self.capture(ctx, command),
+            Direction::Restore => self.reapply(ctx, command),
+        }
+    }
+
+    /// Runs `command` and writes its output into the store folder.
+    fn capture(&self, ctx: &C, command: &Command) -> Result<(), VacuumError> {
+        let output = match self.run(ctx, command, None)? {
+            Some(output) => output,
+            None => return Ok(()),
+        };
+
+        if let Some(file_name) = &command.output {
+            let target = ctx.stored().join(file_name);
+            fs::create_dir_all(target.parent().expect("Failed to get parent directory"))?;
+            self.backup(&target, None)?;
+            std::fs::write(target.as_path(), output)?;
+            self.written.borrow_mut().insert(target);
+        }
+        Ok(())
+    }
+
+    /// Runs the `on restore` command of `command` with the output captured during store.
+    fn reapply(&self, ctx: &C, command: &Command) -> Result<(), VacuumError> {
+        if command.on_restore.is_none() {
+            return Ok(());
+        }
+
+        let captured = match &command.output {
+            Some(file_name) => {
+                let source = ctx.stored().join(file_name);
+                if !source.is_file() {
+                    self.report.borrow_mut().skipped += 1;
+                    return Ok(());
+                }
+                Some(fs::read_to_string(source)?)
+            }
+            None => None,
+        };
+
+        for (restore, input) in process::restore_commands(command, captured.as_deref()) {
+            let result = self.run(ctx, &restore, input.as_deref()).map(|_| ());
+            self.attempt(&restore.command, result)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `command` in the live directory and returns its standard output.
+    fn run(
+        &self,
+        ctx: &C,
+        command: &Command,
+        input: Option<&str>,
+    ) -> Result<Option<String>, VacuumError> {
+        let dir = ctx.live();
+        if !dir.is_dir() {
+            self.report.borrow_mut().skipped += 1;
+            self.logger.print(format!(
+                "{} {}: {} does not exist",
+                "Skip".yellow(),
+                command.command,
+                dir.display()
+            ));
+            return Ok(None);
+        }
+
+        let result = match process::run(command, &dir, input) {
+            Err(VacuumError::CommandNotFound(_)) if command.optional => {
+                self.report.borrow_mut().skipped += 1;
+                self.logger.print(format!(
+                    "{} {}: program is not installed",
+                    "Skip".yellow(),
+                    command.command
+                ));
+                return Ok(None);
+            }
+            result => result?,
+        };
+
+        if !result.status.success() {
+            return Err(VacuumError::CommandFailed {
+                command: command.command.clone(),
+                status: result.status,
+                stderr: String::from_utf8_lossy(&result.stderr).trim().to_owned(),
+            });
+        }
+
+        self.report.borrow_mut().commands += 1;
+        self.logger.print(format!(
+            "{} ({}) {}",
+            "Execute".blue(),
+            command.mode,
+            command.command
+        ));
+        Ok(Some(String::from_utf8(result.stdout).unwrap_or_default()))
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+pub(crate) fn same_content(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(ma), Ok(mb)) if ma.is_file() && mb.is_file() && ma.len() == mb.len() => {
+            matches!((fs::read(a), fs::read(b)), (Ok(ca), Ok(cb)) if ca == cb)
+        }
+        _ => false,
+    }
 }
 
 impl<C> Handler for FileSystemExecutor<C>
 where
-    C: Context<Current = (PathBuf, PathBuf)>,
+    C: PairedContext,
 {
     type Context = C;
 
-    fn handle_file<S: AsRef<str>>(
+    fn handle_file(
         &self,
         ctx: &Self::Context,
-        file_name: S,
         _: &Option<Vec<DependencyCheck>>,
     ) -> Result<(), VacuumError> {
-        let (source, target) = ctx.sub(file_name.as_ref()).current();
+        let (source, target) = ctx.current();
         if !source.exists() {
+            self.report.borrow_mut().skipped += 1;
             return Ok(());
         }
 
-        let parent = target.parent().unwrap();
-        if fs::create_dir_all(parent).is_ok() {
-            self.logger
-                .print(format!("{} {}", "Copy".blue(), source.display()));
-            return fs::copy(source.as_path(), target.as_path())
-                .map_err(VacuumError::IoError)
-                .map(|_| ());
-        }
-        Ok(())
+        let result = self.copy(ctx.direction(), &source, &target);
+        self.attempt(source.display().to_string(), result)
     }
 
     fn handle_files<S: AsRef<str>>(
         &self,
         ctx: &Self::Context,
         pattern: S,
+        exclude: &[String],
     ) -> Result<(), VacuumError> {
-        for found in ctx.search(pattern.as_ref()) {
+        for found in ctx.search(pattern.as_ref(), exclude) {
             let (source, target) = found.current();
             if source.is_dir() {
                 continue;
             }
-            let dest_dir = target.parent().expect("Failed to get parent directory");
+            let result = self.copy(ctx.direction(), &source, &target);
+            self.attempt(source.display().to_string(), result)?;
+        }
+        Ok(())
+    }
 
-            fs::create_dir_all(&dest_dir)
-                .and_then(|_| fs::copy(source.as_path(), target.as_path()))
-                .and_then(|_| {
-                    self.logger
-                        .print(format!("{} {}", "Copy".blue(), source.display()));
-                    Ok(())
-                })?;
+    fn handle_dir(&self, ctx: &Self::Context, dir: &Directory) -> Result<(), VacuumError> {
+        let ctx = ctx.sub(&dir.name);
+        let (source, target) = ctx.current();
+        if !source.is_dir() {
+            self.report.borrow_mut().skipped += 1;
+            return Ok(());
+        }
+
+        fs::create_dir_all(&target)?;
+        for entry in tree::walk(&source, dir.symlinks, &dir.exclude)? {
+            if ctx.is_ignored(
+                &ctx.stored().join(&entry.path),
+                entry.kind == EntryKind::Dir,
+            ) {
+                continue;
+            }
+            let (from, to) = (source.join(&entry.path), target.join(&entry.path));
+            let result = match &entry.kind {
+                EntryKind::Dir => fs::create_dir_all(&to).map_err(VacuumError::from),
+                EntryKind::File => self.copy(ctx.direction(), &from, &to),
+                EntryKind::Symlink(original) => self.replace_with_link(original, &to),
+            };
+            self.attempt(from.display().to_string(), result)?;
         }
         Ok(())
     }
 
-    fn handle_execute<S: AsRef<str>>(
-        &self,
-        ctx: &Self::Context,
-        command: S,
-        file_name: &Option<String>,
-    ) -> Result<(), VacuumError> {
-        let command = command.as_ref();
-        let mut args = if cfg!(windows) {
-            vec!["cmd", "/c"]
-        } else {
-            vec![]
-        };
-        args.extend(command.split_whitespace().collect::<Vec<_>>());
+    fn handle_execute(&self, ctx: &Self::Context, command: &Command) -> Result<(), VacuumError> {
+        let result = self.execute(ctx, command);
+        self.attempt(&command.command, result)
+    }
+}
 
-        let result = std::process::Command::new(args[0])
-            .args(&args[1..])
-            .output()?;
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::adapters::context::restore_context::RestoreContext;
+    use crate::adapters::context::store_context::StoreContext;
+    use std::path::PathBuf;
 
-        if result.status.success() {
-            self.logger
-                .print(format!("{} {} ", "Execute".blue(), command));
-            let output = String::from_utf8(result.stdout).unwrap_or_default();
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vacuum-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 
-            if let Some(file_name) = file_name {
-                let (_, mut target) = ctx.current();
-                target.push(file_name);
-                std::fs::write(target.as_path(), output)?;
-            }
+    #[test]
+    fn test_store_through_link_keeps_stored_file() {
+        let dir = temp_dir("store-link");
+        let (live, stored) = (dir.join("live"), dir.join("stored"));
+        fs::write(&stored, "content").unwrap();
+        tree::symlink(&stored, &live).unwrap();
 
-            return Ok(());
-        }
-        // return the error here
-        Ok(())
+        let executor = FileSystemExecutor::<StoreContext>::new("test".into());
+        executor.copy(Direction::Store, &live, &stored).unwrap();
+
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "content");
+        assert!(executor.written().contains(&stored));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_over_link_replaces_it_with_copy() {
+        let dir = temp_dir("restore-link");
+        let (live, stored) = (dir.join("live"), dir.join("stored"));
+        fs::write(&stored, "content").unwrap();
+        tree::symlink(&stored, &live).unwrap();
+
+        let journal = Rc::new(Journal::new(dir.join("backups")));
+        let executor =
+            FileSystemExecutor::<RestoreContext>::new("test".into()).with_journal(journal);
+        executor.copy(Direction::Restore, &stored, &live).unwrap();
+
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "content");
+        assert_eq!(fs::read_to_string(&live).unwrap(), "content");
+        assert!(!is_symlink(&live));
+        fs::remove_dir_all(dir).unwrap();
     }
 }
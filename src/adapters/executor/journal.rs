@@ -0,0 +1,200 @@
+use crate::application::error::VacuumError;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+
+const JOURNAL_FILE: &str = "journal";
+const FILES_DIR: &str = "files";
+
+/// A single change made to a live file during restore.
+#[derive(Debug, PartialEq)]
+pub enum JournalEntry {
+    /// `target` existed and was moved to `backup` before being overwritten.
+    Backup(PathBuf, PathBuf),
+    /// `target` did not exist and was created by restore.
+    Create(PathBuf),
+}
+
+impl JournalEntry {
+    fn to_line(&self) -> String {
+        match self {
+            JournalEntry::Backup(target, backup) => {
+                format!("backup\t{}\t{}", target.display(), backup.display())
+            }
+            JournalEntry::Create(target) => format!("create\t{}", target.display()),
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.split('\t');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("backup"), Some(target), Some(backup)) => {
+                Some(JournalEntry::Backup(target.into(), backup.into()))
+            }
+            (Some("create"), Some(target), None) => Some(JournalEntry::Create(target.into())),
+            _ => None,
+        }
+    }
+}
+
+/// Backs up the live files restore overwrites so that the restore can be rolled back.
+pub struct Journal {
+    root: PathBuf,
+    session: RefCell<Option<(PathBuf, File)>>,
+    recorded: RefCell<BTreeSet<PathBuf>>,
+}
+
+impl Journal {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            session: RefCell::new(None),
+            recorded: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Default location of the backup sessions.
+    pub fn default_root() -> PathBuf {
+        let mut root = dirs_next::data_local_dir().unwrap_or_default();
+        root.push("vacuum");
+        root.push("backups");
+        root
+    }
+
+    /// Moves `target` into the backup directory before it is overwritten.
+    pub fn record(&self, target: &Path) -> Result<(), VacuumError> {
+        // a second backup would replace the original with what restore wrote
+        if !self.recorded.borrow_mut().insert(target.to_path_buf()) {
+            return Ok(());
+        }
+        let mut session = self.session.borrow_mut();
+        if session.is_none() {
+            *session = Some(self.open_session()?);
+        }
+        let (dir, file) = session.as_mut().unwrap();
+
+        let entry = if target.is_file() {
+            let mut backup = dir.join(FILES_DIR);
+            backup.extend(target.components().filter_map(|c| match c {
+                Component::Normal(part) => Some(part),
+                _ => None,
+            }));
+            fs::create_dir_all(backup.parent().unwrap())?;
+            move_file(target, &backup)?;
+            JournalEntry::Backup(target.to_path_buf(), backup)
+        } else {
+            JournalEntry::Create(target.to_path_buf())
+        };
+
+        writeln!(file, "{}", entry.to_line())?;
+        Ok(())
+    }
+
+    fn open_session(&self) -> Result<(PathBuf, File), VacuumError> {
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut dir = self.root.join(&timestamp);
+        let mut counter = 1;
+        while dir.exists() {
+            dir = self.root.join(format!("{}-{}", timestamp, counter));
+            counter += 1;
+        }
+        fs::create_dir_all(&dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(JOURNAL_FILE))?;
+        Ok((dir, file))
+    }
+
+    /// Returns the most recent session directory under `root`.
+    pub fn latest_session(root: &Path) -> Result<Option<PathBuf>, VacuumError> {
+        if !root.exists() {
+            return Ok(None);
+        }
+        let mut sessions = fs::read_dir(root)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.join(JOURNAL_FILE).is_file())
+            .collect::<Vec<_>>();
+        sessions.sort();
+        Ok(sessions.pop())
+    }
+
+    /// Reads the entries of a session in the order they were recorded.
+    pub fn entries(session: &Path) -> Result<Vec<JournalEntry>, VacuumError> {
+        let file = File::open(session.join(JOURNAL_FILE))?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Some(entry) = JournalEntry::from_line(&line?) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+pub(crate) fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_line_round_trip() {
+        let entry = JournalEntry::Backup("/home/u/.bashrc".into(), "/b/files/.bashrc".into());
+
+        assert_eq!(entry.to_line(), "backup\t/home/u/.bashrc\t/b/files/.bashrc");
+        assert_eq!(JournalEntry::from_line(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn test_create_line_round_trip() {
+        let entry = JournalEntry::Create("/home/u/.config/fish/config.fish".into());
+
+        assert_eq!(entry.to_line(), "create\t/home/u/.config/fish/config.fish");
+        assert_eq!(JournalEntry::from_line(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(JournalEntry::from_line(""), None);
+        assert_eq!(JournalEntry::from_line("backup\t/only/target"), None);
+        assert_eq!(JournalEntry::from_line("create\t/a\t/b"), None);
+        assert_eq!(JournalEntry::from_line("delete\t/a"), None);
+    }
+
+    #[test]
+    fn test_record_target_once_per_session() {
+        let root = std::env::temp_dir().join(format!("vacuum-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let target = root.join("config");
+        fs::write(&target, "original").unwrap();
+
+        let journal = Journal::new(root.join("backups"));
+        journal.record(&target).unwrap();
+        fs::write(&target, "first").unwrap();
+        journal.record(&target).unwrap();
+        fs::write(&target, "second").unwrap();
+
+        let session = Journal::latest_session(&root.join("backups"))
+            .unwrap()
+            .unwrap();
+        match Journal::entries(&session).unwrap().as_slice() {
+            [JournalEntry::Backup(backed_up, backup)] => {
+                assert_eq!(backed_up, &target);
+                assert_eq!(fs::read_to_string(backup).unwrap(), "original");
+            }
+            entries => panic!("unexpected entries {:?}", entries),
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
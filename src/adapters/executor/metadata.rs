@@ -0,0 +1,241 @@
+use crate::application::error::VacuumError;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Name of the metadata file kept in every app directory of the store folder.
+pub const METADATA_FILE: &str = ".vacuum-metadata";
+
+/// What a plain copy loses of a live file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileMetadata {
+    /// Permission bits, only known on unix.
+    pub mode: Option<u32>,
+    /// Modification time in seconds since the epoch.
+    pub modified: Option<u64>,
+    /// Where the live file pointed to when it was a link.
+    pub link: Option<PathBuf>,
+}
+
+impl FileMetadata {
+    /// Reads the metadata of the live file at `path`.
+    pub fn read(path: &Path) -> Result<Self, VacuumError> {
+        let link = fs::read_link(path).ok();
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+        Ok(Self {
+            mode: mode_of(&metadata),
+            modified,
+            link,
+        })
+    }
+
+    /// Sets the modification time and the mode of the file at `path`.
+    pub fn apply(&self, path: &Path) -> io::Result<()> {
+        if let Some(modified) = self.modified {
+            // Windows needs write access to change the time, unix only needs
+            // to own the file, which keeps read-only files working.
+            let file = File::options().read(true).write(cfg!(windows)).open(path)?;
+            file.set_modified(UNIX_EPOCH + Duration::from_secs(modified))?;
+        }
+        if let Some(mode) = self.mode {
+            set_mode(path, mode)?;
+        }
+        Ok(())
+    }
+
+    fn to_line(&self, path: &Path) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            path.display(),
+            self.mode
+                .map(|mode| format!("{:o}", mode))
+                .unwrap_or_default(),
+            self.modified
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            self.link
+                .as_ref()
+                .map(|link| link.display().to_string())
+                .unwrap_or_default()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<(PathBuf, Self)> {
+        let mut parts = line.split('\t');
+        let (path, mode, modified, link) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let metadata = Self {
+            mode: u32::from_str_radix(mode, 8).ok(),
+            modified: modified.parse().ok(),
+            link: Some(link)
+                .filter(|link| !link.is_empty())
+                .map(PathBuf::from),
+        };
+        Some((path.into(), metadata))
+    }
+}
+
+/// Modes, modification times and link targets of the files of an app.
+pub struct Metadata {
+    app_dir: PathBuf,
+    entries: RefCell<BTreeMap<PathBuf, FileMetadata>>,
+}
+
+impl Metadata {
+    /// Starts an empty record for the files of `app_dir`.
+    pub fn new(app_dir: PathBuf) -> Self {
+        Self {
+            app_dir,
+            entries: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Reads the metadata file of `app_dir`, a missing one records nothing.
+    pub fn load(app_dir: PathBuf) -> Result<Self, VacuumError> {
+        let metadata = Self::new(app_dir);
+        let path = metadata.app_dir.join(METADATA_FILE);
+        if path.is_file() {
+            let entries = fs::read_to_string(path)?
+                .lines()
+                .filter_map(FileMetadata::from_line)
+                .collect();
+            metadata.entries.replace(entries);
+        }
+        Ok(metadata)
+    }
+
+    /// Remembers `metadata` for `stored`, a path inside the app directory.
+    pub fn record(&self, stored: &Path, metadata: FileMetadata) {
+        if let Ok(path) = stored.strip_prefix(&self.app_dir) {
+            self.entries
+                .borrow_mut()
+                .insert(path.to_path_buf(), metadata);
+        }
+    }
+
+    /// Returns what was recorded for `stored`.
+    pub fn get(&self, stored: &Path) -> Option<FileMetadata> {
+        let path = stored.strip_prefix(&self.app_dir).ok()?;
+        self.entries.borrow().get(path).cloned()
+    }
+
+    /// Whether `path` resolves to a file inside the app directory.
+    pub fn is_stored(&self, path: &Path) -> bool {
+        match (fs::canonicalize(path), fs::canonicalize(&self.app_dir)) {
+            (Ok(path), Ok(app_dir)) => path.starts_with(app_dir),
+            _ => false,
+        }
+    }
+
+    /// Forgets the files for which `keep` returns false.
+    pub fn retain<F: Fn(&Path) -> bool>(&self, keep: F) {
+        let app_dir = &self.app_dir;
+        self.entries
+            .borrow_mut()
+            .retain(|path, _| keep(&app_dir.join(path)));
+    }
+
+    /// Writes the metadata file, removing it when nothing was recorded.
+    pub fn save(&self) -> Result<(), VacuumError> {
+        let path = self.app_dir.join(METADATA_FILE);
+        let entries = self.entries.borrow();
+        if entries.is_empty() {
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        let mut content = String::new();
+        for (stored, metadata) in entries.iter() {
+            content.push_str(&metadata.to_line(stored));
+            content.push('\n');
+        }
+        fs::create_dir_all(&self.app_dir)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+/// Whether `stored` kept the mode of `metadata`, some filesystems do not.
+pub fn keeps_mode(stored: &Path, metadata: &FileMetadata) -> bool {
+    match (
+        fs::metadata(stored).ok().and_then(|m| mode_of(&m)),
+        metadata.mode,
+    ) {
+        (Some(stored), Some(live)) => stored == live,
+        _ => true,
+    }
+}
+
+#[cfg(unix)]
+fn mode_of(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode_of(_: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_: &Path, _: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_round_trip() {
+        let metadata = FileMetadata {
+            mode: Some(0o600),
+            modified: Some(1577923200),
+            link: Some("../dots/rc".into()),
+        };
+        let line = metadata.to_line(Path::new("home/.ssh/config"));
+
+        assert_eq!(line, "home/.ssh/config\t600\t1577923200\t../dots/rc");
+        assert_eq!(
+            FileMetadata::from_line(&line),
+            Some(("home/.ssh/config".into(), metadata))
+        );
+    }
+
+    #[test]
+    fn test_line_without_values() {
+        let line = FileMetadata::default().to_line(Path::new("a"));
+
+        assert_eq!(line, "a\t\t\t");
+        assert_eq!(
+            FileMetadata::from_line(&line),
+            Some(("a".into(), FileMetadata::default()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert_eq!(FileMetadata::from_line(""), None);
+        assert_eq!(FileMetadata::from_line("a\t600\t1"), None);
+        assert_eq!(
+            FileMetadata::from_line("a\tnot-octal\tnot-a-number\t"),
+            Some(("a".into(), FileMetadata::default()))
+        );
+    }
+}
@@ -1,4 +1,14 @@
+mod dry_run_executor;
 mod file_system_executor;
+mod journal;
 mod logger;
+mod metadata;
+mod process;
 
+pub use dry_run_executor::DryRunExecutor;
+pub(crate) use file_system_executor::same_content;
 pub use file_system_executor::FileSystemExecutor;
+pub(crate) use journal::move_file;
+pub use journal::{Journal, JournalEntry};
+pub(crate) use logger::Logger;
+pub use metadata::{Metadata, METADATA_FILE};
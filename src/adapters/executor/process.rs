@@ -0,0 +1,209 @@
+use crate::application::error::VacuumError;
+use crate::domain::{Command, CommandMode};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{self, ExitStatus, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Placeholder of `on restore` commands that are run once per captured line.
+pub const LINE_PLACEHOLDER: &str = "{line}";
+
+/// Builds the program and arguments to run for a command.
+pub fn command_line(command: &Command) -> Result<Vec<String>, VacuumError> {
+    let mut args: Vec<String> = match command.mode {
+        CommandMode::Shell if cfg!(windows) => vec!["cmd".into(), "/C".into()],
+        CommandMode::Shell => vec!["sh".into(), "-c".into()],
+        CommandMode::Args if cfg!(windows) => vec!["cmd".into(), "/c".into()],
+        CommandMode::Args => vec![],
+    };
+
+    match command.mode {
+        CommandMode::Shell => args.push(command.command.clone()),
+        CommandMode::Args => {
+            let words = shell_words::split(&command.command)
+                .map_err(|e| VacuumError::InvalidCommand(command.command.clone(), e.to_string()))?;
+            if words.is_empty() {
+                return Err(VacuumError::InvalidCommand(
+                    command.command.clone(),
+                    "empty command".into(),
+                ));
+            }
+            args.extend(words);
+        }
+    }
+    Ok(args)
+}
+
+/// The time a command is allowed to run for, `None` when it may run forever.
+pub fn timeout(command: &Command) -> Option<Duration> {
+    command.timeout.map(Duration::from_secs)
+}
+
+/// Builds the commands that reapply the output `captured` by `command` during restore.
+pub fn restore_commands(
+    command: &Command,
+    captured: Option<&str>,
+) -> Vec<(Command, Option<String>)> {
+    let restore = match &command.on_restore {
+        Some(restore) => restore,
+        None => return vec![],
+    };
+    let with_command = |line: String| Command {
+        command: line,
+        mode: restore.mode,
+        output: None,
+        optional: command.optional,
+        timeout: command.timeout,
+        env: command.env.clone(),
+        on_restore: None,
+    };
+
+    match captured {
+        Some(captured) if restore.command.contains(LINE_PLACEHOLDER) => captured
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let quoted = shell_words::quote(line);
+                (
+                    with_command(restore.command.replace(LINE_PLACEHOLDER, &quoted)),
+                    None,
+                )
+            })
+            .collect(),
+        Some(captured) => vec![(
+            with_command(restore.command.clone()),
+            Some(captured.to_owned()),
+        )],
+        None => vec![(with_command(restore.command.clone()), None)],
+    }
+}
+
+/// Runs a command in `dir` with `input` as its standard input, killing it on timeout.
+pub fn run(command: &Command, dir: &Path, input: Option<&str>) -> Result<Output, VacuumError> {
+    if !dir.is_dir() {
+        return Err(VacuumError::IoError(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("working directory {} does not exist", dir.display()),
+        )));
+    }
+
+    let args = command_line(command)?;
+    let mut child = process::Command::new(&args[0])
+        .args(&args[1..])
+        .current_dir(dir)
+        .envs(command.env.iter().map(|(key, value)| (key, value)))
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => VacuumError::CommandNotFound(command.command.clone()),
+            _ => e.into(),
+        })?;
+
+    // the pipes are drained while waiting so that a chatty command does not
+    // block on a full pipe until it times out
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        let input = input.to_owned();
+        // a command that exits without reading its input only closes the
+        // pipe, which is not an error of ours
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    let timeout = timeout(command);
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(timeout) = timeout.filter(|timeout| started.elapsed() >= *timeout) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(VacuumError::CommandTimedOut {
+                command: command.command.clone(),
+                timeout,
+            });
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    if not_found(command, status) {
+        return Err(VacuumError::CommandNotFound(command.command.clone()));
+    }
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// commands run through a shell never fail to spawn for a missing program,
+// the shell exits with 127 (sh) or 9009 (cmd) instead, which a failing
+// script may return too, so only optional commands are taken as not found
+fn not_found(command: &Command, status: ExitStatus) -> bool {
+    command.optional
+        && match command.mode {
+            _ if cfg!(windows) => status.code() == Some(9009),
+            CommandMode::Shell => status.code() == Some(127),
+            CommandMode::Args => false,
+        }
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str, mode: CommandMode, optional: bool) -> Command {
+        Command {
+            command: command.into(),
+            mode,
+            output: None,
+            optional,
+            timeout: None,
+            env: vec![],
+            on_restore: None,
+        }
+    }
+
+    #[test]
+    fn test_missing_program_is_not_found() {
+        for mode in [CommandMode::Args, CommandMode::Shell] {
+            let result = run(
+                &command("vacuum-missing-tool", mode, true),
+                Path::new("."),
+                None,
+            );
+
+            assert!(matches!(result, Err(VacuumError::CommandNotFound(_))));
+        }
+    }
+
+    #[test]
+    fn test_missing_program_of_required_shell_command_fails() {
+        let command = command("vacuum-missing-tool", CommandMode::Shell, false);
+        let output = run(&command, Path::new("."), None).unwrap();
+
+        assert!(!output.status.success());
+        assert!(!output.stderr.is_empty());
+    }
+}
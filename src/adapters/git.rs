@@ -0,0 +1,191 @@
+use crate::application::error::VacuumError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Returns the root of the git repository `folder` is in.
+pub fn repository_root(folder: &Path) -> Result<Option<PathBuf>, VacuumError> {
+    if !folder.is_dir() {
+        return Ok(None);
+    }
+    match git(folder, ["rev-parse", "--show-toplevel"]) {
+        Ok(output) => Ok(Some(PathBuf::from(
+            String::from_utf8_lossy(&output).trim_end(),
+        ))),
+        Err(VacuumError::CommandFailed { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Commits the changes to the `touched` files and nothing else staged.
+pub fn commit(
+    root: &Path,
+    touched: &[(String, Vec<PathBuf>)],
+) -> Result<Option<String>, VacuumError> {
+    let root = root.canonicalize()?;
+    let mut apps = BTreeMap::new();
+    for (app, files) in touched {
+        for file in files {
+            if let Some(path) = relative_to(&root, file) {
+                apps.insert(path, app.as_str());
+            }
+        }
+    }
+
+    let mut changes = BTreeMap::<&str, Vec<(&str, PathBuf)>>::new();
+    for (change, path) in status(&root)? {
+        if let Some(app) = apps.get(&path) {
+            changes.entry(app).or_default().push((change, path));
+        }
+    }
+    if changes.is_empty() {
+        return Ok(None);
+    }
+
+    let paths = changes
+        .values()
+        .flatten()
+        .map(|(_, path)| path.clone().into_os_string())
+        .collect::<BTreeSet<_>>();
+    git_with_paths(&root, &["add", "--all"], &paths)?;
+
+    let message = message(&changes);
+    git_with_paths(&root, &["commit", "--quiet", "--message", &message], &paths)?;
+    Ok(Some(message))
+}
+
+fn message(changes: &BTreeMap<&str, Vec<(&str, PathBuf)>>) -> String {
+    let names = changes.keys().copied().collect::<Vec<_>>();
+    let mut message = format!("Store {}\n", names.join(", "));
+    for (app, files) in changes {
+        message.push_str(&format!("\n{}:\n", app));
+        for (change, path) in files {
+            message.push_str(&format!("  {} {}\n", change, path.display()));
+        }
+    }
+    message
+}
+
+/// Lists the changed paths of the repository at `root` relative to it.
+fn status(root: &Path) -> Result<Vec<(&'static str, PathBuf)>, VacuumError> {
+    let output = git(
+        root,
+        ["status", "--porcelain", "-z", "--untracked-files=all"],
+    )?;
+    Ok(parse_status(&String::from_utf8_lossy(&output)))
+}
+
+fn parse_status(output: &str) -> Vec<(&'static str, PathBuf)> {
+    let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+    let mut changes = Vec::new();
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        // renames and copies are followed by the path they came from
+        if code.starts_with(['R', 'C']) {
+            entries.next();
+        }
+        let change = if code.starts_with(['?', 'A']) {
+            "added"
+        } else if code.contains('D') {
+            "deleted"
+        } else {
+            "modified"
+        };
+        changes.push((change, PathBuf::from(path)));
+    }
+    changes
+}
+
+fn relative_to(root: &Path, file: &Path) -> Option<PathBuf> {
+    // the file may have been pruned, so only its parent can be canonicalized
+    let canonical = file.parent()?.ancestors().find_map(|dir| {
+        let rest = file.strip_prefix(dir).ok()?;
+        Some(dir.canonicalize().ok()?.join(rest))
+    })?;
+    canonical.strip_prefix(root).ok().map(Path::to_path_buf)
+}
+
+fn git_with_paths(
+    root: &Path,
+    args: &[&str],
+    paths: &BTreeSet<OsString>,
+) -> Result<Vec<u8>, VacuumError> {
+    let mut all = args.iter().map(OsString::from).collect::<Vec<_>>();
+    all.push("--".into());
+    all.extend(paths.iter().cloned());
+    git(root, all)
+}
+
+fn git<I, S>(dir: &Path, args: I) -> Result<Vec<u8>, VacuumError>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<OsString>,
+{
+    let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+    let output = Command::new("git")
+        .arg("--literal-pathspecs")
+        .args(&args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => VacuumError::CommandNotFound("git".to_owned()),
+            _ => e.into(),
+        })?;
+
+    if !output.status.success() {
+        let args = args
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>();
+        return Err(VacuumError::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let output = "?? cfg/nvim/init.vim\0 M cfg/fish/config.fish\0AM cfg/git/config\0 D cfg/old\0D  cfg/gone\0";
+
+        assert_eq!(
+            parse_status(output),
+            vec![
+                ("added", "cfg/nvim/init.vim".into()),
+                ("modified", "cfg/fish/config.fish".into()),
+                ("added", "cfg/git/config".into()),
+                ("deleted", "cfg/old".into()),
+                ("deleted", "cfg/gone".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_status_rename() {
+        let output = "R  cfg/new name\0cfg/old name\0 M cfg/a\0";
+
+        assert_eq!(
+            parse_status(output),
+            vec![
+                ("modified", "cfg/new name".into()),
+                ("modified", "cfg/a".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_status() {
+        assert_eq!(parse_status(""), vec![]);
+    }
+}
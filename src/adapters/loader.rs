@@ -0,0 +1,148 @@
+use crate::adapters::embedded::EMBEDDED_APPS;
+use crate::adapters::parsers::pom_parser::PomParser;
+use crate::application::error::VacuumError;
+use crate::application::parser::VacuumFileParser;
+use crate::domain::{Action, App};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prefix of the names of the embedded files in messages.
+const EMBEDDED_PREFIX: &str = "<embedded>/";
+
+const APP_EXTENSION: &str = "vacuum";
+
+/// A place app definitions are read from.
+pub enum Source {
+    /// The definitions bundled into the binary at build time.
+    Embedded,
+    Dir(PathBuf),
+}
+
+/// Loads app definitions, later sources take precedence over earlier ones.
+pub struct DefinitionLoader {
+    sources: Vec<Source>,
+}
+
+impl DefinitionLoader {
+    pub fn new(sources: Vec<Source>) -> Self {
+        Self { sources }
+    }
+
+    /// The default search path, from the embedded definitions to `apps_dir` when given.
+    pub fn search_path(apps_dir: Option<PathBuf>) -> Vec<Source> {
+        let mut sources = vec![Source::Embedded];
+        if let Some(mut config_dir) = dirs_next::config_dir() {
+            config_dir.push("vacuum");
+            config_dir.push("apps");
+            sources.push(Source::Dir(config_dir));
+        }
+        sources.push(Source::Dir(PathBuf::from("./apps")));
+        sources.extend(apps_dir.map(Source::Dir));
+        sources
+    }
+
+    pub fn load(&self) -> Result<Vec<App>, VacuumError> {
+        let mut apps: Vec<App> = Vec::new();
+        for source in &self.sources {
+            for (file_name, content) in read_source(source)? {
+                for mut app in PomParser::parse(&file_name, content)? {
+                    let actions = std::mem::take(&mut app.actions);
+                    app.actions = resolve_includes(actions, &mut vec![file_name.clone()])?;
+                    match apps.iter_mut().find(|existing| existing.name == app.name) {
+                        Some(existing) => *existing = app,
+                        None => apps.push(app),
+                    }
+                }
+            }
+        }
+        Ok(apps)
+    }
+}
+
+/// Replaces `include` actions with the actions of the included files.
+fn resolve_includes(
+    actions: Vec<Action>,
+    stack: &mut Vec<String>,
+) -> Result<Vec<Action>, VacuumError> {
+    let mut resolved = Vec::with_capacity(actions.len());
+    for action in actions {
+        match action {
+            Action::Include(name) => {
+                let including = stack.last().unwrap();
+                let (file_name, content) = read_include(including, &name)?;
+                if stack.contains(&file_name) {
+                    stack.push(file_name);
+                    return Err(VacuumError::IncludeCycle(stack.clone()));
+                }
+                let included = PomParser::parse_fragment(&file_name, content)?;
+                stack.push(file_name);
+                resolved.extend(resolve_includes(included, stack)?);
+                stack.pop();
+            }
+            Action::Context(folder, sub_actions) => resolved.push(Action::Context(
+                folder,
+                resolve_includes(sub_actions, stack)?,
+            )),
+            Action::When(condition, sub_actions) => resolved.push(Action::When(
+                condition,
+                resolve_includes(sub_actions, stack)?,
+            )),
+            action => resolved.push(action),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Reads a file included from `including`, looking next to it before the embedded ones.
+fn read_include(including: &str, name: &str) -> Result<(String, String), VacuumError> {
+    if !including.starts_with(EMBEDDED_PREFIX) {
+        let path = Path::new(including).with_file_name(name);
+        if path.is_file() {
+            let content = fs::read_to_string(&path)?;
+            return Ok((path.display().to_string(), content));
+        }
+    }
+    EMBEDDED_APPS
+        .iter()
+        .find(|(file_name, _)| *file_name == name)
+        .map(|(file_name, content)| (embedded_name(file_name), content.to_string()))
+        .ok_or_else(|| VacuumError::IncludeNotFound(name.to_owned(), including.to_owned()))
+}
+
+fn embedded_name(file_name: &str) -> String {
+    format!("{}{}", EMBEDDED_PREFIX, file_name)
+}
+
+/// Reads the name and content of every file in a source.
+fn read_source(source: &Source) -> Result<Vec<(String, String)>, VacuumError> {
+    let dir = match source {
+        Source::Embedded => {
+            return Ok(EMBEDDED_APPS
+                .iter()
+                .filter(|(file_name, _)| is_app_file(Path::new(file_name)))
+                .map(|(file_name, content)| (embedded_name(file_name), content.to_string()))
+                .collect())
+        }
+        Source::Dir(dir) if dir.is_dir() => dir,
+        Source::Dir(_) => return Ok(Vec::new()),
+    };
+
+    let mut files = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_app_file(path))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut contents = Vec::new();
+    for file in files {
+        let content = fs::read_to_string(&file)?;
+        contents.push((file.display().to_string(), content));
+    }
+    Ok(contents)
+}
+
+// fragments meant to be included have a different extension
+fn is_app_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == APP_EXTENSION)
+}
@@ -1,4 +1,8 @@
 pub mod context;
+pub mod embedded;
 pub mod executor;
+pub mod git;
+pub mod loader;
 pub mod parsers;
+pub mod tree;
 pub mod use_cases;
@@ -1,16 +1,115 @@
-use crate::application::error::VacuumError;
+use crate::application::error::{ParseError, VacuumError};
 use crate::application::parser::VacuumFileParser;
-use crate::domain::{Action, App, Dependency, DependencyCheck, Folder};
+use crate::domain::{
+    Action, App, Command, CommandMode, Condition, Dependency, DependencyCheck, Directory, Folder,
+    KnownFolder, RestoreCommand, SymlinkPolicy,
+};
+use glob::Pattern;
 use pom::parser::*;
+use std::cell::RefCell;
 use std::iter::FromIterator;
 
 pub struct PomParser;
 
 impl VacuumFileParser for PomParser {
-    fn parse(input: String) -> Result<App, VacuumError> {
-        let input = input.chars().collect::<Vec<_>>();
-        let result = parse_vacuum_file().parse(&input);
-        result.map_err(VacuumError::ParseError)
+    fn parse(file_name: &str, input: String) -> Result<Vec<App>, VacuumError> {
+        parse_with(file_name, input, |input| parse_vacuum_file().parse(input))
+    }
+
+    fn parse_fragment(file_name: &str, input: String) -> Result<Vec<Action>, VacuumError> {
+        parse_with(file_name, input, |input| parse_fragment().parse(input))
+    }
+}
+
+fn parse_with<O>(
+    file_name: &str,
+    input: String,
+    parse: impl FnOnce(&[char]) -> pom::Result<O>,
+) -> Result<O, VacuumError> {
+    let input = input.chars().collect::<Vec<_>>();
+    FURTHEST_FAILURE.with(|furthest| *furthest.borrow_mut() = (0, Vec::new()));
+    parse(&input).map_err(|e| VacuumError::ParseError(parse_error(file_name, &input, e)))
+}
+
+thread_local! {
+    /// The furthest position a token failed to match at and the tokens tried there.
+    static FURTHEST_FAILURE: RefCell<(usize, Vec<String>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+/// Tracks failures of `parser` under `name`, literal tokens should be quoted.
+fn expected<'a, O: 'a, S: Into<String>>(
+    name: S,
+    parser: Parser<'a, char, O>,
+) -> Parser<'a, char, O> {
+    let name = name.into();
+    Parser::new(move |input: &'a [char], start: usize| {
+        let result = parser.parse_at(input, start);
+        if result.is_err() {
+            FURTHEST_FAILURE.with(|furthest| {
+                let (position, names) = &mut *furthest.borrow_mut();
+                if start > *position {
+                    *position = start;
+                    names.clear();
+                }
+                if start == *position && !names.contains(&name) {
+                    names.push(name.clone());
+                }
+            });
+        }
+        result
+    })
+}
+
+fn keyword<'a>(word: &'static str) -> Parser<'a, char, &'a str> {
+    expected(format!("`{}`", word), tag(word))
+}
+
+fn symbol<'a>(c: char) -> Parser<'a, char, char> {
+    expected(format!("`{}`", c), sym(c))
+}
+
+fn parse_error(file_name: &str, input: &[char], error: pom::Error) -> ParseError {
+    let (mut position, expected) = FURTHEST_FAILURE.with(|furthest| furthest.borrow().clone());
+    if expected.is_empty() {
+        position = match error {
+            pom::Error::Mismatch { position, .. }
+            | pom::Error::Conversion { position, .. }
+            | pom::Error::Expect { position, .. }
+            | pom::Error::Custom { position, .. } => position,
+            pom::Error::Incomplete => input.len(),
+        };
+    }
+    let position = position.min(input.len());
+
+    let line_start = input[..position]
+        .iter()
+        .rposition(|c| *c == '\n')
+        .map_or(0, |i| i + 1);
+    let line_end = input[position..]
+        .iter()
+        .position(|c| *c == '\n')
+        .map_or(input.len(), |i| position + i);
+    let line = input[..line_start].iter().filter(|c| **c == '\n').count() + 1;
+
+    let found = input[position..]
+        .iter()
+        .take_while(|c| !c.is_whitespace())
+        .collect::<String>();
+    let found = match (found.is_empty(), input.get(position)) {
+        (false, _) => Some(found),
+        (true, Some(c)) => Some(c.escape_default().to_string()),
+        (true, None) => None,
+    };
+
+    ParseError {
+        file: file_name.to_owned(),
+        line,
+        column: position - line_start + 1,
+        source_line: String::from_iter(&input[line_start..line_end])
+            .trim_end()
+            .to_owned(),
+        found,
+        expected,
     }
 }
 
@@ -19,105 +118,232 @@ fn space<'a>() -> Parser<'a, char, ()> {
 }
 
 fn ident<'a>() -> Parser<'a, char, String> {
-    none_of("\\\", \r\n\t[]{}()")
-        .repeat(1..)
-        .map(String::from_iter)
+    expected("name", none_of("\\\", \r\n\t[]{}()").repeat(1..)).map(String::from_iter)
 }
 
 fn string<'a>() -> Parser<'a, char, String> {
-    let char_string = none_of("\\\"").repeat(0..).map(String::from_iter);
-    sym('\"') * char_string - sym('\"')
+    let escaped = sym('\\') * one_of("\\\"");
+    let char_string = (none_of("\\\"") | escaped)
+        .repeat(0..)
+        .map(String::from_iter);
+    expected("string", sym('\"')) * char_string - symbol('\"')
 }
 
 fn dependency_exists<'a>() -> Parser<'a, char, DependencyCheck> {
-    (tag("exists") * space() * tag("->") * space() * ident() - space())
+    (keyword("exists") * space() * keyword("->") * space() * ident() - space())
         .name("dependency_exists")
         .map(DependencyCheck::Exists)
 }
 
 fn dependency_contains<'a>() -> Parser<'a, char, DependencyCheck> {
-    (tag("contains") * space() * string() + space() * tag("->") * space() * ident() - space())
-        .name("dependency_contains")
-        .map(|(pattern, dep_name)| DependencyCheck::Contains(pattern, dep_name))
+    (keyword("contains") * space() * string() + space() * keyword("->") * space() * ident()
+        - space())
+    .name("dependency_contains")
+    .map(|(pattern, dep_name)| DependencyCheck::Contains(pattern, dep_name))
 }
 
 fn dependencies<'a>() -> Parser<'a, char, Vec<DependencyCheck>> {
     let dependency_rules = dependency_exists() | dependency_contains();
 
-    let items = list(dependency_rules, sym(',') * space());
-    let dependencies = space() * sym('[') * space() * items - space() * sym(']');
+    let items = list(dependency_rules, symbol(',') * space());
+    let dependencies = space() * symbol('[') * space() * items - space() * symbol(']');
     dependencies.name("dependencies")
 }
 
 fn command_file<'a>() -> Parser<'a, char, Action> {
-    (tag("file") * space() * string() + space() * dependencies().opt())
-        .map(|(f, d)| Action::File(f, d))
+    let stored_as = space() * keyword("as") * space() * string();
+    (keyword("file") * space() * string() + stored_as.opt() + space() * dependencies().opt())
+        .map(|((f, a), d)| Action::File(f, a, d))
 }
 
 fn command_files<'a>() -> Parser<'a, char, Action> {
-    (tag("files") * space() * string()).map(Action::Files)
+    let files = keyword("files") * space() * string() + (space() * excludes()).opt();
+    files.map(|(pattern, exclude)| Action::Files(pattern, exclude.unwrap_or_default()))
+}
+
+fn glob_pattern<'a>() -> Parser<'a, char, String> {
+    let pattern = string().convert(|pattern| Pattern::new(&pattern).map(|_| pattern));
+    expected("glob pattern", pattern)
+}
+
+fn excludes<'a>() -> Parser<'a, char, Vec<String>> {
+    let items = list(glob_pattern(), symbol(',') * space());
+    let patterns = symbol('[') * space() * items - space() * symbol(']');
+    let excludes = keyword("exclude") * space() * (patterns | glob_pattern().map(|p| vec![p]));
+    excludes.name("exclude")
+}
+
+fn command_dir<'a>() -> Parser<'a, char, Action> {
+    let policy = keyword("preserve").map(|_| SymlinkPolicy::Preserve)
+        | keyword("follow").map(|_| SymlinkPolicy::Follow)
+        | keyword("skip").map(|_| SymlinkPolicy::Skip);
+    let symlinks =
+        symbol('[') * space() * keyword("symlinks") * space() * policy - space() * symbol(']');
+    let dir = keyword("dir") * space() * string()
+        + (space() * symlinks).opt()
+        + (space() * excludes()).opt();
+
+    dir.map(|((name, symlinks), exclude)| {
+        Action::Dir(Directory {
+            name,
+            symlinks: symlinks.unwrap_or(SymlinkPolicy::Preserve),
+            exclude: exclude.unwrap_or_default(),
+        })
+    })
+}
+
+enum CommandOption {
+    Optional,
+    Timeout(u64),
+}
+
+fn number<'a>() -> Parser<'a, char, u64> {
+    let digits = expected("number", one_of("0123456789").repeat(1..));
+    digits.convert(|digits| String::from_iter(digits).parse::<u64>())
+}
+
+fn command_options<'a>() -> Parser<'a, char, Vec<CommandOption>> {
+    let optional = keyword("optional").map(|_| CommandOption::Optional);
+    let timeout = (keyword("timeout") * space() * number()).map(CommandOption::Timeout);
+    let option = optional | timeout;
+
+    let items = list(option, symbol(',') * space());
+    let options = symbol('[') * space() * items - space() * symbol(']');
+    options.name("command_options")
+}
+
+fn command_env<'a>() -> Parser<'a, char, Vec<(String, String)>> {
+    let variable = ident() - space() + string();
+
+    let variables = list(variable, space());
+    let env = keyword("env") * space() * symbol('{') * space() * variables - space() * symbol('}');
+    env.name("env")
 }
 
 fn command_exec<'a>() -> Parser<'a, char, Action> {
-    let command = tag("execute") | tag("exec");
-    let command = command * space() * string() + (space() * tag(">>") * space() * string()).opt();
+    let mode = keyword("execute").map(|_| CommandMode::Args)
+        | keyword("exec").map(|_| CommandMode::Args)
+        | keyword("shell").map(|_| CommandMode::Shell);
+    let command = mode - space()
+        + string()
+        + (space() * keyword(">>") * space() * string()).opt()
+        + (space() * command_options()).opt()
+        + (space() * command_env()).opt()
+        + (space() * command_on_restore()).opt();
 
-    command.map(|(command, file_name)| Action::Execute(command, file_name))
+    command.map(
+        |(((((mode, command), output), options), env), on_restore)| {
+            let mut command = Command {
+                command,
+                mode,
+                output,
+                optional: false,
+                timeout: None,
+                env: env.unwrap_or_default(),
+                on_restore,
+            };
+            for option in options.unwrap_or_default() {
+                match option {
+                    CommandOption::Optional => command.optional = true,
+                    CommandOption::Timeout(seconds) => command.timeout = Some(seconds),
+                }
+            }
+            Action::Execute(command)
+        },
+    )
 }
 
-fn context_home<'a>() -> Parser<'a, char, Action> {
-    tag("home")
-        * space()
-        * call(parse_actions)
-            .map(|actions| Action::Context(Folder::Home, actions))
-            .name("home")
+fn command_on_restore<'a>() -> Parser<'a, char, RestoreCommand> {
+    let mode = keyword("shell").map(|_| CommandMode::Shell) - space();
+    let command = keyword("on") * space() * keyword("restore") * space() * mode.opt() + string();
+    command.map(|(mode, command)| RestoreCommand {
+        command,
+        mode: mode.unwrap_or(CommandMode::Args),
+    })
 }
 
-fn context_config<'a>() -> Parser<'a, char, Action> {
-    tag("config")
-        * space()
-        * call(parse_actions)
-            .map(|actions| Action::Context(Folder::Config, actions))
-            .name("config")
+fn context_known<'a>() -> Parser<'a, char, Action> {
+    let mut folders = KnownFolder::ALL.iter().map(|folder| {
+        let folder = *folder;
+        keyword(folder.name()).map(move |_| folder)
+    });
+    let first = folders.next().unwrap();
+    let folder = folders.fold(first, |choice, folder| choice | folder);
+    let f = folder - space() + call(parse_actions).name("folder");
+    f.map(|(folder, actions)| Action::Context(Folder::Known(folder), actions))
 }
 
-fn context_local<'a>() -> Parser<'a, char, Action> {
-    tag("local")
-        * space()
-        * call(parse_actions)
-            .map(|actions| Action::Context(Folder::Local, actions))
-            .name("local")
+fn context_root<'a>() -> Parser<'a, char, Action> {
+    let f = keyword("root") * space() * string() + space() * call(parse_actions).name("root");
+    f.map(|(path, actions)| Action::Context(Folder::Root(path), actions))
 }
 
 fn context_search<'a>() -> Parser<'a, char, Action> {
-    let f = tag("search") * space() * string() + space() * call(parse_actions).name("search");
-    f.map(|(pattern, actions)| Action::Context(Folder::Search(pattern), actions))
+    let f = keyword("search") * space() * string()
+        + (space() * excludes()).opt()
+        + space() * call(parse_actions).name("search");
+    f.map(|((pattern, exclude), actions)| {
+        Action::Context(
+            Folder::Search(pattern, exclude.unwrap_or_default()),
+            actions,
+        )
+    })
 }
 
 fn context_custom<'a>() -> Parser<'a, char, Action> {
-    let f = tag("cd") * space() * string() + space() * call(parse_actions).name("custom");
+    let f = keyword("cd") * space() * string() + space() * call(parse_actions).name("custom");
     f.map(|(folder, actions)| Action::Context(Folder::Custom(folder), actions))
 }
 
-fn parse_actions<'a>() -> Parser<'a, char, Vec<Action>> {
-    let item = command_file()
+fn command_let<'a>() -> Parser<'a, char, Action> {
+    let variable = keyword("let") * space() * ident() - space() - symbol('=') - space() + string();
+    variable.map(|(name, value)| Action::Let(name, value))
+}
+
+fn context_when<'a>() -> Parser<'a, char, Action> {
+    let os = keyword("os") * space() * string().map(Condition::Os);
+    let hostname = keyword("hostname") * space() * string().map(Condition::Hostname);
+    let env = keyword("env") * space() * string().map(Condition::Env);
+    let condition = keyword("when") * space() * (os | hostname | env);
+    let f = condition + space() * call(parse_actions).name("when");
+    f.map(|(condition, actions)| Action::When(condition, actions))
+}
+
+fn command_include<'a>() -> Parser<'a, char, Action> {
+    (keyword("include") * space() * string()).map(Action::Include)
+}
+
+fn action<'a>() -> Parser<'a, char, Action> {
+    command_include()
+        | command_file()
         | command_files()
+        | command_dir()
         | command_exec()
-        | context_home()
-        | context_config()
-        | context_local()
+        | context_known()
+        | context_root()
         | context_search()
-        | context_custom();
+        | context_when()
+        | context_custom()
+}
 
-    let items = list(item, sym(';').opt() * space());
-    let actions = sym('{') * space() * items - space() * sym('}');
+fn action_list<'a>() -> Parser<'a, char, Vec<Action>> {
+    list(action(), sym(';').opt() * space())
+}
+
+fn parse_actions<'a>() -> Parser<'a, char, Vec<Action>> {
+    let actions = symbol('{') * space() * action_list() - space() * symbol('}');
     actions.name("actions")
 }
 
+fn app_actions<'a>() -> Parser<'a, char, Vec<Action>> {
+    let actions = list(command_let() | action(), sym(';').opt() * space());
+    (symbol('{') * space() * actions - space() * symbol('}')).name("actions")
+}
+
 fn dependency_rule<'a>() -> Parser<'a, char, Dependency> {
     let block = none_of("}").repeat(1..).map(String::from_iter);
 
-    (space() * ident() + space() * sym('{') * block - space() * sym('}'))
+    (space() * ident() + space() * symbol('{') * block - space() * symbol('}'))
         .map(|(name, block)| Dependency { name, block })
         .name("dependency_rule")
 }
@@ -125,14 +351,15 @@ fn dependency_rule<'a>() -> Parser<'a, char, Dependency> {
 fn parse_dependencies_section<'a>() -> Parser<'a, char, Vec<Dependency>> {
     let dependency_rules = list(dependency_rule(), space()).name("dependency_rules");
     let dependencies_section =
-        space() * tag("dependencies") * space() * sym('{') * dependency_rules - space() * sym('}');
+        space() * keyword("dependencies") * space() * symbol('{') * dependency_rules
+            - space() * symbol('}');
 
     dependencies_section.name("dependencies_section")
 }
 
-fn parse_vacuum_file<'a>() -> Parser<'a, char, App> {
-    let app = space() * tag("app") * space() * string()
-        + space() * call(parse_actions)
+fn app_definition<'a>() -> Parser<'a, char, App> {
+    let app = space() * keyword("app") * space() * string()
+        + space() * call(app_actions)
         + parse_dependencies_section().opt();
 
     app.map(|((name, actions), dependencies)| App {
@@ -142,6 +369,14 @@ fn parse_vacuum_file<'a>() -> Parser<'a, char, App> {
     })
 }
 
+fn parse_vacuum_file<'a>() -> Parser<'a, char, Vec<App>> {
+    space() * app_definition().repeat(1..) - space() - expected("end of file", end())
+}
+
+fn parse_fragment<'a>() -> Parser<'a, char, Vec<Action>> {
+    space() * action_list() - space() - expected("end of file", end())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,8 +493,16 @@ mod tests {
             Ok(Action::Context(
                 Folder::Custom("WebStorm".into()),
                 vec![
-                    Action::File("*.xml".into(), None),
-                    Action::Execute("ls files".into(), None)
+                    Action::File("*.xml".into(), None, None),
+                    Action::Execute(Command {
+                        command: "ls files".into(),
+                        mode: CommandMode::Args,
+                        output: None,
+                        optional: false,
+                        timeout: None,
+                        env: vec![],
+                        on_restore: None,
+                    })
                 ]
             ))
         )
@@ -278,10 +521,18 @@ mod tests {
         assert_eq!(
             r,
             Ok(Action::Context(
-                Folder::Search(".WebStorm*".into()),
+                Folder::Search(".WebStorm*".into(), vec![]),
                 vec![
-                    Action::File("*.xml".into(), None),
-                    Action::Execute("ls files".into(), None)
+                    Action::File("*.xml".into(), None, None),
+                    Action::Execute(Command {
+                        command: "ls files".into(),
+                        mode: CommandMode::Args,
+                        output: None,
+                        optional: false,
+                        timeout: None,
+                        env: vec![],
+                        on_restore: None,
+                    })
                 ]
             ))
         )
@@ -296,14 +547,22 @@ mod tests {
         .chars()
         .collect::<Vec<_>>();
 
-        let r = context_home().parse(&input);
+        let r = context_known().parse(&input);
         assert_eq!(
             r,
             Ok(Action::Context(
-                Folder::Home,
+                Folder::Known(KnownFolder::Home),
                 vec![
-                    Action::File("*.xml".into(), None),
-                    Action::Execute("ls files".into(), None)
+                    Action::File("*.xml".into(), None, None),
+                    Action::Execute(Command {
+                        command: "ls files".into(),
+                        mode: CommandMode::Args,
+                        output: None,
+                        optional: false,
+                        timeout: None,
+                        env: vec![],
+                        on_restore: None,
+                    })
                 ]
             ))
         )
@@ -318,12 +577,20 @@ mod tests {
         .chars()
         .collect::<Vec<_>>();
 
-        let r = parse_actions().parse(&input);
+        let r = parse_actions().parse(input);
         assert_eq!(
             r,
             Ok(vec![
-                Action::File("*.xml".into(), None),
-                Action::Execute("ls files".into(), None)
+                Action::File("*.xml".into(), None, None),
+                Action::Execute(Command {
+                    command: "ls files".into(),
+                    mode: CommandMode::Args,
+                    output: None,
+                    optional: false,
+                    timeout: None,
+                    env: vec![],
+                    on_restore: None,
+                })
             ])
         )
     }
@@ -332,7 +599,23 @@ mod tests {
     fn test_parse_file() {
         let input = r#"file "keyboard.xml""#.chars().collect::<Vec<_>>();
         let r = command_file().parse(&input);
-        assert_eq!(r, Ok(Action::File("keyboard.xml".into(), None)))
+        assert_eq!(r, Ok(Action::File("keyboard.xml".into(), None, None)))
+    }
+
+    #[test]
+    fn test_parse_file_as() {
+        let input = r#"file "settings.json" as "settings-oss.json" [exists -> dep1]"#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_file().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::File(
+                "settings.json".into(),
+                Some("settings-oss.json".into()),
+                Some(vec![DependencyCheck::Exists("dep1".into())]),
+            ))
+        )
     }
 
     #[test]
@@ -346,6 +629,7 @@ mod tests {
             r,
             Ok(Action::File(
                 "keyboard.xml".into(),
+                None,
                 Some(vec![
                     DependencyCheck::Exists("dep1".into()),
                     DependencyCheck::Contains("plug".into(), "dep2".into())
@@ -354,18 +638,175 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_parse_context_when() {
+        let input = r#"when os "linux" {
+            when hostname "desktop" { file "a" }
+            when env "WAYLAND_DISPLAY" { file "b" }
+        }"#
+        .chars()
+        .collect::<Vec<_>>();
+        let r = context_when().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::When(
+                Condition::Os("linux".into()),
+                vec![
+                    Action::When(
+                        Condition::Hostname("desktop".into()),
+                        vec![Action::File("a".into(), None, None)]
+                    ),
+                    Action::When(
+                        Condition::Env("WAYLAND_DISPLAY".into()),
+                        vec![Action::File("b".into(), None, None)]
+                    ),
+                ]
+            ))
+        )
+    }
+
+    #[test]
+    fn test_parse_context_known_and_root() {
+        let input = r#"{
+            cache { file "a" }
+            documents { file "b" }
+            root "/etc" { file "c" }
+        }"#
+        .chars()
+        .collect::<Vec<_>>();
+        let r = parse_actions().parse(&input);
+        assert_eq!(
+            r,
+            Ok(vec![
+                Action::Context(
+                    Folder::Known(KnownFolder::Cache),
+                    vec![Action::File("a".into(), None, None)]
+                ),
+                Action::Context(
+                    Folder::Known(KnownFolder::Documents),
+                    vec![Action::File("b".into(), None, None)]
+                ),
+                Action::Context(
+                    Folder::Root("/etc".into()),
+                    vec![Action::File("c".into(), None, None)]
+                ),
+            ])
+        )
+    }
+
+    #[test]
+    fn test_parse_let() {
+        let input = r#"let version = "2020.1""#.chars().collect::<Vec<_>>();
+        let r = command_let().parse(&input);
+        assert_eq!(r, Ok(Action::Let("version".into(), "2020.1".into())))
+    }
+
+    #[test]
+    fn test_parse_let_only_at_app_level() {
+        let input = r#"app "a" { let v = "1" home { file "${v}" } }"#;
+        assert!(PomParser::parse("a.vacuum", input.into()).is_ok());
+
+        let input = r#"app "a" { home { let v = "1" } }"#;
+        assert!(PomParser::parse("a.vacuum", input.into()).is_err());
+    }
+
     #[test]
     fn test_parse_files() {
         let input = r#"files "*.xml""#.chars().collect::<Vec<_>>();
         let r = command_files().parse(&input);
-        assert_eq!(r, Ok(Action::Files("*.xml".into())))
+        assert_eq!(r, Ok(Action::Files("*.xml".into(), vec![])))
+    }
+
+    #[test]
+    fn test_parse_files_with_exclude() {
+        let input = r#"files "*.xml" exclude ["*.lock", "workspace.xml"]"#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_files().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Files(
+                "*.xml".into(),
+                vec!["*.lock".into(), "workspace.xml".into()]
+            ))
+        )
+    }
+
+    #[test]
+    fn test_parse_context_search_with_exclude() {
+        let input = r#"search ".WebStorm*" exclude "*.bak" {
+            files "*.xml"
+        }"#
+        .chars()
+        .collect::<Vec<_>>();
+        let r = context_search().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Context(
+                Folder::Search(".WebStorm*".into(), vec!["*.bak".into()]),
+                vec![Action::Files("*.xml".into(), vec![])]
+            ))
+        )
+    }
+
+    #[test]
+    fn test_parse_invalid_exclude_pattern() {
+        let input = r#"app "x" { files "*" exclude "a[" }"#;
+        let e = PomParser::parse("x.vacuum", input.into());
+        match e {
+            Err(VacuumError::ParseError(e)) => {
+                assert!(e.expected.contains(&"glob pattern".to_owned()))
+            }
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        let input = r#"dir "fish""#.chars().collect::<Vec<_>>();
+        let r = command_dir().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Dir(Directory {
+                name: "fish".into(),
+                symlinks: SymlinkPolicy::Preserve,
+                exclude: vec![],
+            }))
+        )
+    }
+
+    #[test]
+    fn test_parse_dir_with_symlinks_and_exclude() {
+        let input = r#"dir "fish" [symlinks follow] exclude ["fish_variables", "*.bak"]"#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_dir().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Dir(Directory {
+                name: "fish".into(),
+                symlinks: SymlinkPolicy::Follow,
+                exclude: vec!["fish_variables".into(), "*.bak".into()],
+            }))
+        )
     }
 
     #[test]
     fn test_parse_execute() {
         let input = r#"exec "ls home""#.chars().collect::<Vec<_>>();
         let r = command_exec().parse(&input);
-        assert_eq!(r, Ok(Action::Execute("ls home".into(), None)))
+        assert_eq!(
+            r,
+            Ok(Action::Execute(Command {
+                command: "ls home".into(),
+                mode: CommandMode::Args,
+                output: None,
+                optional: false,
+                timeout: None,
+                env: vec![],
+                on_restore: None,
+            }))
+        )
     }
 
     #[test]
@@ -374,7 +815,101 @@ mod tests {
         let r = command_exec().parse(&input);
         assert_eq!(
             r,
-            Ok(Action::Execute("ls home".into(), Some("output.txt".into())))
+            Ok(Action::Execute(Command {
+                command: "ls home".into(),
+                mode: CommandMode::Args,
+                output: Some("output.txt".into()),
+                optional: false,
+                timeout: None,
+                env: vec![],
+                on_restore: None,
+            }))
+        )
+    }
+
+    #[test]
+    fn test_parse_shell_with_escaped_quotes() {
+        let input = r#"shell "dconf dump / | grep \"name=\\\\x\"" >> "dconf.txt""#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_exec().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Execute(Command {
+                command: r#"dconf dump / | grep "name=\\x""#.into(),
+                mode: CommandMode::Shell,
+                output: Some("dconf.txt".into()),
+                optional: false,
+                timeout: None,
+                env: vec![],
+                on_restore: None,
+            }))
+        )
+    }
+
+    #[test]
+    fn test_parse_execute_optional() {
+        let input = r#"exec "ls home" >> "output.txt" [optional]"#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_exec().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Execute(Command {
+                command: "ls home".into(),
+                mode: CommandMode::Args,
+                output: Some("output.txt".into()),
+                optional: true,
+                timeout: None,
+                env: vec![],
+                on_restore: None,
+            }))
+        )
+    }
+
+    #[test]
+    fn test_parse_execute_with_timeout_and_env() {
+        let input = r#"exec "ls home" [timeout 5, optional] env {
+            LANG "C"
+            LS_COLORS ""
+        }"#
+        .chars()
+        .collect::<Vec<_>>();
+        let r = command_exec().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Execute(Command {
+                command: "ls home".into(),
+                mode: CommandMode::Args,
+                output: None,
+                optional: true,
+                timeout: Some(5),
+                env: vec![("LANG".into(), "C".into()), ("LS_COLORS".into(), "".into())],
+                on_restore: None,
+            }))
+        )
+    }
+
+    #[test]
+    fn test_parse_execute_with_on_restore() {
+        let input = r#"exec "code --list-extensions" >> "extensions.txt" [optional] on restore "code --install-extension {line}""#
+            .chars()
+            .collect::<Vec<_>>();
+        let r = command_exec().parse(&input);
+        assert_eq!(
+            r,
+            Ok(Action::Execute(Command {
+                command: "code --list-extensions".into(),
+                mode: CommandMode::Args,
+                output: Some("extensions.txt".into()),
+                optional: true,
+                timeout: None,
+                env: vec![],
+                on_restore: Some(RestoreCommand {
+                    command: "code --install-extension {line}".into(),
+                    mode: CommandMode::Args,
+                }),
+            }))
         )
     }
 
@@ -401,29 +936,29 @@ mod tests {
         let r = parse_vacuum_file().parse(&input);
         assert_eq!(
             r,
-            Ok(App {
+            Ok(vec![App {
                 name: "webstorm".into(),
                 actions: vec![Action::Context(
-                    Folder::Home,
+                    Folder::Known(KnownFolder::Home),
                     vec![Action::Context(
-                        Folder::Search(".WebStorm*".into()),
+                        Folder::Search(".WebStorm*".into(), vec![]),
                         vec![Action::Context(
                             Folder::Custom("config".into()),
                             vec![
                                 Action::Context(
                                     Folder::Custom("keymaps".into()),
-                                    vec![Action::Files("*.xml".into())],
+                                    vec![Action::Files("*.xml".into(), vec![])],
                                 ),
                                 Action::Context(
                                     Folder::Custom("options".into()),
-                                    vec![Action::File("editor.xml".into(), None)],
+                                    vec![Action::File("editor.xml".into(), None, None)],
                                 ),
                             ],
                         )],
                     )],
                 )],
                 dependencies: None
-            })
+            }])
         );
     }
 
@@ -451,12 +986,13 @@ mod tests {
         let r = parse_vacuum_file().parse(&input);
         assert_eq!(
             r,
-            Ok(App {
+            Ok(vec![App {
                 name: "some-app".into(),
                 actions: vec![Action::Context(
-                    Folder::Home,
+                    Folder::Known(KnownFolder::Home),
                     vec![Action::File(
                         "some-app.config".into(),
+                        None,
                         Some(vec![
                             DependencyCheck::Exists("dep1".into()),
                             DependencyCheck::Contains("content".into(), "dep2".into())
@@ -479,7 +1015,81 @@ mod tests {
                         .into(),
                     }
                 ])
-            })
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple_apps_and_include() {
+        let input = r#"
+            app "idea" {
+                include "jetbrains.vacuum"
+            }
+            app "clion" {
+                home { include "jetbrains.vacuum" }
+            }"#;
+        let r = PomParser::parse("jetbrains-ides.vacuum", input.into()).unwrap();
+        assert_eq!(r.len(), 2);
+        assert_eq!(r[0].name, "idea");
+        assert_eq!(
+            r[1].actions,
+            vec![Action::Context(
+                Folder::Known(KnownFolder::Home),
+                vec![Action::Include("jetbrains.vacuum".into())]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_fragment() {
+        let input = r#"
+            file "a"
+            cd "b" { files "*" }
+        "#;
+        let r = PomParser::parse_fragment("fragment.inc", input.into());
+        assert_eq!(
+            r.unwrap(),
+            vec![
+                Action::File("a".into(), None, None),
+                Action::Context(
+                    Folder::Custom("b".into()),
+                    vec![Action::Files("*".into(), vec![])]
+                ),
+            ]
         );
+        assert!(PomParser::parse("fragment.vacuum", input.into()).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let input = "app \"bad\" {\n    home {\n        fle \".x\"\n    }\n}\n";
+        let r = PomParser::parse("bad.vacuum", input.into());
+
+        match r {
+            Err(VacuumError::ParseError(e)) => {
+                assert_eq!(e.file, "bad.vacuum");
+                assert_eq!((e.line, e.column), (3, 9));
+                assert_eq!(e.source_line, "        fle \".x\"");
+                assert_eq!(e.found, Some("fle".into()));
+                assert!(e.expected.contains(&"`file`".to_owned()));
+                assert!(e.expected.contains(&"`}`".to_owned()));
+            }
+            r => panic!("expected a parse error, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_trailing_input() {
+        let input = "app \"bad\" {\n}\nextra";
+        let r = PomParser::parse("bad.vacuum", input.into());
+
+        match r {
+            Err(VacuumError::ParseError(e)) => {
+                assert_eq!((e.line, e.column), (3, 1));
+                assert_eq!(e.found, Some("extra".into()));
+                assert!(e.expected.contains(&"end of file".to_owned()));
+            }
+            r => panic!("expected a parse error, got {:?}", r),
+        }
     }
 }
@@ -0,0 +1,138 @@
+use crate::application::error::VacuumError;
+use crate::domain::SymlinkPolicy;
+use glob::Pattern;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, PartialEq)]
+pub enum EntryKind {
+    Dir,
+    File,
+    /// A link that is kept as a link, with the path it points at.
+    Symlink(PathBuf),
+}
+
+/// An entry found below the root of a walk.
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    /// Path of the entry relative to the root.
+    pub path: PathBuf,
+    pub kind: EntryKind,
+}
+
+/// Lists everything below `root` sorted by path, leaving out the `exclude` patterns.
+pub fn walk(
+    root: &Path,
+    symlinks: SymlinkPolicy,
+    exclude: &[String],
+) -> Result<Vec<Entry>, VacuumError> {
+    let exclude = patterns(exclude)?;
+    let mut walker = Walker {
+        root,
+        symlinks,
+        exclude,
+        visited: HashSet::new(),
+        entries: Vec::new(),
+    };
+    if root.is_dir() {
+        walker.visit(root)?;
+    }
+    Ok(walker.entries)
+}
+
+struct Walker<'a> {
+    root: &'a Path,
+    symlinks: SymlinkPolicy,
+    exclude: Vec<Pattern>,
+    /// Directories already walked, so that followed links cannot loop.
+    visited: HashSet<PathBuf>,
+    entries: Vec<Entry>,
+}
+
+impl<'a> Walker<'a> {
+    fn visit(&mut self, dir: &Path) -> Result<(), VacuumError> {
+        if !self.visited.insert(dir.canonicalize()?) {
+            return Ok(());
+        }
+
+        let mut children = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        children.sort();
+
+        for child in children {
+            let path = child.strip_prefix(self.root).unwrap().to_path_buf();
+            if excluded(&self.exclude, &path) {
+                continue;
+            }
+
+            let metadata = fs::symlink_metadata(&child)?;
+            if metadata.file_type().is_symlink() {
+                match self.symlinks {
+                    SymlinkPolicy::Skip => continue,
+                    SymlinkPolicy::Preserve => {
+                        let kind = EntryKind::Symlink(fs::read_link(&child)?);
+                        self.entries.push(Entry { path, kind });
+                        continue;
+                    }
+                    // a dangling link has nothing to follow
+                    SymlinkPolicy::Follow if !child.exists() => continue,
+                    SymlinkPolicy::Follow => {}
+                }
+            }
+
+            if child.is_dir() {
+                self.entries.push(Entry {
+                    path,
+                    kind: EntryKind::Dir,
+                });
+                self.visit(&child)?;
+            } else {
+                self.entries.push(Entry {
+                    path,
+                    kind: EntryKind::File,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compiles `exclude` patterns for `excluded`.
+pub fn patterns(exclude: &[String]) -> Result<Vec<Pattern>, VacuumError> {
+    exclude
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern)
+                .map_err(|e| VacuumError::InvalidPattern(pattern.clone(), e.msg.to_owned()))
+        })
+        .collect()
+}
+
+/// Whether the relative `path` or its name matches one of the patterns.
+pub fn excluded(exclude: &[Pattern], path: &Path) -> bool {
+    let name = path.file_name().map(Path::new);
+    exclude.iter().any(|pattern| {
+        pattern.matches_path(path) || name.is_some_and(|name| pattern.matches_path(name))
+    })
+}
+
+/// Creates a symbolic link at `link` pointing at `original`.
+pub fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(original, link);
+
+    #[cfg(windows)]
+    {
+        let resolved = link
+            .parent()
+            .map_or(original.to_path_buf(), |p| p.join(original));
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(original, link)
+        } else {
+            std::os::windows::fs::symlink_file(original, link)
+        }
+    }
+}
@@ -2,9 +2,10 @@ use crate::adapters::context::deps_context::TargetDirectoryContext;
 use crate::application::context::Context;
 use crate::application::error::VacuumError;
 use crate::application::executor;
+use crate::application::report::Report;
 use crate::application::usecase::UseCase;
 use crate::application::Handler;
-use crate::domain::{App, DependencyCheck};
+use crate::domain::{App, Command, DependencyCheck, Directory};
 use colored::*;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
@@ -80,41 +81,43 @@ impl<'a> DependencyAnalyzer<'a> {
 impl<'a> Handler for DependencyAnalyzer<'a> {
     type Context = TargetDirectoryContext;
 
-    fn handle_file<S: AsRef<str>>(
+    fn handle_file(
         &self,
         ctx: &Self::Context,
-        file_name: S,
         dependency_checks: &Option<Vec<DependencyCheck>>,
     ) -> Result<(), VacuumError> {
-        let mut file_path = ctx.current();
-        file_path.push(file_name.as_ref());
         if let Some(checks) = dependency_checks {
-            self.analyze(file_path, checks)?;
+            self.analyze(ctx.current(), checks)?;
         }
         Ok(())
     }
 
-    fn handle_files<S: AsRef<str>>(&self, _: &Self::Context, _: S) -> Result<(), VacuumError> {
-        Ok(())
-    }
-
-    fn handle_execute<S: AsRef<str>>(
+    fn handle_files<S: AsRef<str>>(
         &self,
         _: &Self::Context,
         _: S,
-        _: &Option<String>,
+        _: &[String],
     ) -> Result<(), VacuumError> {
         Ok(())
     }
+
+    fn handle_dir(&self, _: &Self::Context, _: &Directory) -> Result<(), VacuumError> {
+        Ok(())
+    }
+
+    fn handle_execute(&self, _: &Self::Context, _: &Command) -> Result<(), VacuumError> {
+        Ok(())
+    }
 }
 
 impl UseCase for DepsUseCase {
-    fn run(&self, app: &App) -> Result<(), VacuumError> {
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
         let executor = DependencyAnalyzer::new(app);
         executor::execute(
             &executor,
             &TargetDirectoryContext::new(self.app_dir.clone()),
-            &app,
-        )
+            app,
+        )?;
+        Ok(Report::default())
     }
 }
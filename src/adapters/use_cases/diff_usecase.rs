@@ -0,0 +1,100 @@
+use super::tracked_files::TrackedFilesCollector;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::store_context::StoreContext;
+use crate::adapters::executor::same_content;
+use crate::application::error::VacuumError;
+use crate::application::executor;
+use crate::application::report::Report;
+use crate::application::usecase::UseCase;
+use crate::domain::App;
+use colored::*;
+use similar::TextDiff;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Prints a unified diff of every file tracked by an app.
+pub struct DiffUseCase {
+    app_dir: PathBuf,
+}
+
+impl DiffUseCase {
+    pub fn new(app_dir: PathBuf) -> Self {
+        Self { app_dir }
+    }
+}
+
+/// Reads a file as text, `None` is returned for binary content.
+fn read_text(path: &Path) -> Result<Option<String>, VacuumError> {
+    if !path.is_file() {
+        return Ok(Some(String::new()));
+    }
+    let content = fs::read(path)?;
+    if content.contains(&0) {
+        return Ok(None);
+    }
+    Ok(String::from_utf8(content).ok())
+}
+
+fn header(path: &Path) -> String {
+    if path.is_file() {
+        path.display().to_string()
+    } else {
+        "/dev/null".to_owned()
+    }
+}
+
+impl UseCase for DiffUseCase {
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
+        let collector = TrackedFilesCollector::new();
+        executor::execute(
+            &collector,
+            &StoreContext::new(self.app_dir.clone(), IgnoreFile::for_app(&self.app_dir)?),
+            app,
+        )?;
+
+        for file in collector.into_files() {
+            if same_content(&file.stored, &file.live) {
+                continue;
+            }
+            let (stored, live) = match (read_text(&file.stored)?, read_text(&file.live)?) {
+                (Some(stored), Some(live)) => (stored, live),
+                _ => {
+                    println!(
+                        "{}",
+                        format!(
+                            "Binary files {} and {} differ, skipping",
+                            file.stored.display(),
+                            file.live.display()
+                        )
+                        .bold()
+                    );
+                    continue;
+                }
+            };
+            if stored == live {
+                continue;
+            }
+
+            let diff = TextDiff::from_lines(&stored, &live);
+            let unified = diff
+                .unified_diff()
+                .header(&header(&file.stored), &header(&file.live))
+                .to_string();
+            for line in unified.lines() {
+                let line = if line.starts_with("---") || line.starts_with("+++") {
+                    line.bold()
+                } else if line.starts_with("@@") {
+                    line.cyan()
+                } else if line.starts_with('+') {
+                    line.green()
+                } else if line.starts_with('-') {
+                    line.red()
+                } else {
+                    line.normal()
+                };
+                println!("{}", line);
+            }
+        }
+        Ok(Report::default())
+    }
+}
@@ -0,0 +1,206 @@
+use super::prune::{not_holding_files, stored_files};
+use super::tracked_files::TrackedFilesCollector;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::store_context::StoreContext;
+use crate::adapters::executor::Logger;
+use crate::adapters::tree::{self, EntryKind};
+use crate::application::error::VacuumError;
+use crate::application::executor;
+use crate::domain::{App, SymlinkPolicy};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the manifest file kept in every app directory of the store folder.
+pub const MANIFEST_FILE: &str = ".vacuum-manifest.json";
+
+/// A file written into the store folder by `store`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Where the file was copied from, empty for command outputs.
+    pub source: Option<PathBuf>,
+    /// Path of the file relative to the app directory.
+    pub stored: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// The action that produced the file, e.g. `files "*.json"`.
+    pub action: String,
+}
+
+/// Lists every file of an app directory with its source and content hash.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Builds the manifest of the files `app` has in `app_dir`.
+    pub fn build(app_dir: &Path, app: &App) -> Result<Self, VacuumError> {
+        let mut files = Vec::new();
+        for (source, stored, action) in produced_files(app_dir, app)? {
+            if !stored.is_file() {
+                continue;
+            }
+            let content = fs::read(&stored)?;
+            files.push(ManifestEntry {
+                source,
+                stored: stored
+                    .strip_prefix(app_dir)
+                    .unwrap_or(&stored)
+                    .to_path_buf(),
+                size: content.len() as u64,
+                sha256: sha256(&content),
+                action,
+            });
+        }
+        Ok(Self { files })
+    }
+
+    /// Reads the manifest of `app_dir`, `None` when there is none.
+    pub fn load(app_dir: &Path) -> Result<Option<Self>, VacuumError> {
+        let path = app_dir.join(MANIFEST_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let manifest = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| VacuumError::InvalidManifest(path.display().to_string(), e.to_string()))?;
+        Ok(Some(manifest))
+    }
+
+    /// Writes the manifest into `app_dir`, removing it when it lists nothing.
+    pub fn save(&self, app_dir: &Path) -> Result<(), VacuumError> {
+        let path = app_dir.join(MANIFEST_FILE);
+        if self.files.is_empty() {
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let content = serde_json::to_string_pretty(self).expect("manifest is serializable");
+        fs::write(path, content + "\n")?;
+        Ok(())
+    }
+
+    /// Warns about changed stored files and files none of the actions produce.
+    pub fn verify(&self, app_dir: &Path, app: &App, logger: &Logger) -> Result<(), VacuumError> {
+        for problem in self.problems(app_dir, app)? {
+            logger.print(format!("{} {}", "Warning".yellow(), problem));
+        }
+        Ok(())
+    }
+
+    fn problems(&self, app_dir: &Path, app: &App) -> Result<Vec<String>, VacuumError> {
+        let mut problems = Vec::new();
+        for entry in &self.files {
+            let stored = app_dir.join(&entry.stored);
+            let problem = match fs::read(&stored) {
+                Err(_) => "is missing from the store folder",
+                Ok(content) if sha256(&content) != entry.sha256 => "changed since it was stored",
+                Ok(_) => continue,
+            };
+            problems.push(format!("{} {}", stored.display(), problem));
+        }
+
+        // the live files may not exist yet, so what is produced is found in the store folder
+        let mut produced = stored_files(app_dir, app)?;
+        // at worst files of other machines are reported as not produced
+        produced.extend(not_holding_files(app_dir, app).unwrap_or_default());
+        let ignore = IgnoreFile::for_app(app_dir)?;
+        for entry in tree::walk(app_dir, SymlinkPolicy::Preserve, &[])? {
+            let stored = app_dir.join(&entry.path);
+            if entry.kind == EntryKind::Dir
+                || ignore.is_ignored(&stored, false)
+                || produced.contains(&stored)
+            {
+                continue;
+            }
+            problems.push(format!(
+                "{} is not produced by any action",
+                stored.display()
+            ));
+        }
+        Ok(problems)
+    }
+}
+
+/// Every file the actions of `app` may put into `app_dir`.
+fn produced_files(
+    app_dir: &Path,
+    app: &App,
+) -> Result<Vec<(Option<PathBuf>, PathBuf, String)>, VacuumError> {
+    let collector = TrackedFilesCollector::new();
+    let ctx = StoreContext::new(app_dir.to_path_buf(), IgnoreFile::for_app(app_dir)?);
+    executor::execute(&collector, &ctx, app)?;
+    let (files, outputs) = collector.into_parts();
+    let files = files
+        .into_iter()
+        .map(|file| (Some(file.live), file.stored, file.action));
+    let outputs = outputs
+        .into_iter()
+        .map(|output| (None, output.stored, output.action));
+    Ok(files.chain(outputs).collect())
+}
+
+fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::context::stored_path;
+    use crate::domain::{Action, Folder};
+
+    #[test]
+    fn test_verify_restore_into_empty_live_tree() {
+        let dir = std::env::temp_dir().join(format!("vacuum-manifest-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (live, app_dir) = (dir.join("live"), dir.join("store").join("app"));
+        fs::create_dir_all(&live).unwrap();
+        let stored = app_dir
+            .join("root")
+            .join(stored_path(&live.to_string_lossy()));
+        fs::create_dir_all(stored.join("project")).unwrap();
+        fs::write(stored.join("project").join("config"), "config").unwrap();
+        fs::write(stored.join("settings.json"), "{}").unwrap();
+
+        let app = App {
+            name: "app".into(),
+            actions: vec![Action::Context(
+                Folder::Root(live.to_string_lossy().into_owned()),
+                vec![
+                    Action::Context(
+                        Folder::Search("*".into(), vec![]),
+                        vec![Action::File("config".into(), None, None)],
+                    ),
+                    Action::Files("*.json".into(), vec![]),
+                ],
+            )],
+            dependencies: None,
+        };
+        let manifest = Manifest::build(&app_dir, &app).unwrap();
+
+        assert_eq!(
+            manifest.problems(&app_dir, &app).unwrap(),
+            Vec::<String>::new()
+        );
+
+        fs::write(app_dir.join("stale"), "").unwrap();
+        assert_eq!(
+            manifest.problems(&app_dir, &app).unwrap(),
+            vec![format!(
+                "{} is not produced by any action",
+                app_dir.join("stale").display()
+            )]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
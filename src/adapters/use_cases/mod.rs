@@ -1,7 +1,34 @@
 mod deps_usecase;
+mod diff_usecase;
+mod manifest;
+mod prune;
 mod restore_usecase;
+mod rollback_usecase;
+mod status_usecase;
 mod store_usecase;
+mod tracked_files;
+mod unlink_usecase;
 
 pub use deps_usecase::DepsUseCase;
+pub use diff_usecase::DiffUseCase;
+pub use manifest::MANIFEST_FILE;
 pub use restore_usecase::RestoreUseCase;
+pub use rollback_usecase::RollbackUseCase;
+pub use status_usecase::StatusUseCase;
 pub use store_usecase::StoreUseCase;
+pub use unlink_usecase::UnlinkUseCase;
+
+/// How store and restore should treat the files they touch.
+#[derive(Clone, Copy, Default)]
+pub struct RunOptions {
+    /// Only report what would be done.
+    pub dry_run: bool,
+    /// Carry on with the remaining files when one of them fails.
+    pub keep_going: bool,
+    /// Restore links to the stored files instead of copies.
+    pub link: bool,
+    /// Replace existing files with links.
+    pub force: bool,
+    /// Remove the stored files this run did not store.
+    pub prune: bool,
+}
@@ -0,0 +1,119 @@
+use crate::adapters::context::deps_context::TargetDirectoryContext;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::executor::Logger;
+use crate::adapters::tree::{self, EntryKind};
+use crate::application::context::Context;
+use crate::application::error::VacuumError;
+use crate::application::{executor, Handler};
+use crate::domain::{App, Command, DependencyCheck, Directory, SymlinkPolicy};
+use colored::*;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Removes the files of `app_dir` not in `keep`, only listing them when `dry_run` is set.
+pub fn prune(
+    app_dir: &Path,
+    keep: &BTreeSet<PathBuf>,
+    dry_run: bool,
+    logger: &Logger,
+) -> Result<Vec<PathBuf>, VacuumError> {
+    let ignore = IgnoreFile::for_app(app_dir)?;
+    let mut dirs = Vec::new();
+    let mut pruned = Vec::new();
+    for entry in tree::walk(app_dir, SymlinkPolicy::Preserve, &[])? {
+        let stored = app_dir.join(&entry.path);
+        if entry.kind == EntryKind::Dir {
+            dirs.push(stored);
+            continue;
+        }
+        if keep.contains(&stored) || ignore.is_ignored(&stored, false) {
+            continue;
+        }
+
+        logger.print(format!("{} {}", "Prune".red(), stored.display()));
+        if !dry_run {
+            fs::remove_file(&stored)?;
+        }
+        pruned.push(stored);
+    }
+
+    if !dry_run {
+        // deepest first so that parents emptied by their children go too
+        for dir in dirs.iter().rev() {
+            if fs::read_dir(dir)?.next().is_none() {
+                fs::remove_dir(dir)?;
+            }
+        }
+    }
+    Ok(pruned)
+}
+
+/// The files of `app_dir` the actions of `app` produce, found from the stored side.
+pub fn stored_files(app_dir: &Path, app: &App) -> Result<BTreeSet<PathBuf>, VacuumError> {
+    let collector = StoredFilesCollector {
+        files: RefCell::new(BTreeSet::new()),
+    };
+    let ctx = TargetDirectoryContext::new(app_dir.to_path_buf());
+    executor::execute(&collector, &ctx, app)?;
+    Ok(collector.files.into_inner())
+}
+
+/// The stored files of `app` produced by `when` branches that do not hold here.
+pub fn not_holding_files(app_dir: &Path, app: &App) -> Result<BTreeSet<PathBuf>, VacuumError> {
+    let collector = StoredFilesCollector {
+        files: RefCell::new(BTreeSet::new()),
+    };
+    let ctx = TargetDirectoryContext::new(app_dir.to_path_buf());
+    executor::execute_not_holding(&collector, &ctx, app)?;
+    Ok(collector.files.into_inner())
+}
+
+struct StoredFilesCollector {
+    files: RefCell<BTreeSet<PathBuf>>,
+}
+
+impl Handler for StoredFilesCollector {
+    type Context = TargetDirectoryContext;
+
+    fn handle_file(
+        &self,
+        ctx: &Self::Context,
+        _: &Option<Vec<DependencyCheck>>,
+    ) -> Result<(), VacuumError> {
+        self.files.borrow_mut().insert(ctx.current());
+        Ok(())
+    }
+
+    fn handle_files<S: AsRef<str>>(
+        &self,
+        ctx: &Self::Context,
+        pattern: S,
+        exclude: &[String],
+    ) -> Result<(), VacuumError> {
+        let found = ctx.search(pattern.as_ref(), exclude);
+        self.files
+            .borrow_mut()
+            .extend(found.iter().map(Context::current));
+        Ok(())
+    }
+
+    fn handle_dir(&self, ctx: &Self::Context, dir: &Directory) -> Result<(), VacuumError> {
+        let root = ctx.sub(&dir.name).current();
+        let entries = tree::walk(&root, SymlinkPolicy::Preserve, &dir.exclude)?;
+        self.files
+            .borrow_mut()
+            .extend(entries.into_iter().map(|entry| root.join(entry.path)));
+        Ok(())
+    }
+
+    fn handle_execute(&self, ctx: &Self::Context, command: &Command) -> Result<(), VacuumError> {
+        if let Some(file_name) = &command.output {
+            self.files
+                .borrow_mut()
+                .insert(ctx.current().join(file_name));
+        }
+        Ok(())
+    }
+}
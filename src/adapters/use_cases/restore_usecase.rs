@@ -1,24 +1,52 @@
+use super::manifest::Manifest;
+use super::RunOptions;
+use crate::adapters::context::ignore_file::IgnoreFile;
 use crate::adapters::context::restore_context::RestoreContext;
-use crate::adapters::executor::FileSystemExecutor;
+use crate::adapters::executor::{DryRunExecutor, FileSystemExecutor, Journal, Logger, Metadata};
 use crate::application::error::VacuumError;
 use crate::application::executor;
+use crate::application::report::Report;
 use crate::application::usecase::UseCase;
 use crate::domain::App;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 pub struct RestoreUseCase {
     app_dir: PathBuf,
+    options: RunOptions,
+    journal: Rc<Journal>,
 }
 
 impl RestoreUseCase {
-    pub fn new(app_dir: PathBuf) -> Self {
-        Self { app_dir }
+    pub fn new(app_dir: PathBuf, options: RunOptions, journal: Rc<Journal>) -> Self {
+        Self {
+            app_dir,
+            options,
+            journal,
+        }
     }
 }
 
 impl UseCase for RestoreUseCase {
-    fn run(&self, app: &App) -> Result<(), VacuumError> {
-        let executor = FileSystemExecutor::new(app.name.to_string());
-        executor::execute(&executor, &RestoreContext::new(self.app_dir.clone()), &app)
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
+        if let Some(manifest) = Manifest::load(&self.app_dir)? {
+            let logger = Logger::new(app.name.to_string());
+            manifest.verify(&self.app_dir, app, &logger)?;
+        }
+
+        let ignore = IgnoreFile::for_app(&self.app_dir)?;
+        let ctx = RestoreContext::new(self.app_dir.clone(), ignore);
+        if self.options.dry_run {
+            let executor = DryRunExecutor::new(app.name.to_string()).link(self.options.link);
+            executor::execute(&executor, &ctx, app)?;
+            return Ok(executor.into_report());
+        }
+        let executor = FileSystemExecutor::new(app.name.to_string())
+            .with_journal(self.journal.clone())
+            .with_metadata(Rc::new(Metadata::load(self.app_dir.clone())?))
+            .keep_going(self.options.keep_going)
+            .link(self.options.link, self.options.force);
+        executor::execute(&executor, &ctx, app)?;
+        Ok(executor.into_report())
     }
 }
@@ -0,0 +1,49 @@
+use crate::adapters::executor::{move_file, Journal, JournalEntry, Logger};
+use crate::application::error::VacuumError;
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Undoes the most recent restore by replaying its journal backwards.
+pub struct RollbackUseCase {
+    backups_dir: PathBuf,
+}
+
+impl RollbackUseCase {
+    pub fn new(backups_dir: PathBuf) -> Self {
+        Self { backups_dir }
+    }
+
+    pub fn run(&self) -> Result<(), VacuumError> {
+        let logger = Logger::new("rollback".to_owned());
+        let session = match Journal::latest_session(&self.backups_dir)? {
+            Some(session) => session,
+            None => {
+                logger.print("Nothing to roll back");
+                return Ok(());
+            }
+        };
+
+        logger.print(format!("{} {}", "Session".blue(), session.display()));
+        for entry in Journal::entries(&session)?.iter().rev() {
+            match entry {
+                JournalEntry::Backup(target, backup) => {
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    move_file(backup, target)?;
+                    logger.print(format!("{} {}", "Restore".blue(), target.display()));
+                }
+                JournalEntry::Create(target) => {
+                    if target.is_file() {
+                        fs::remove_file(target)?;
+                        logger.print(format!("{} {}", "Remove".blue(), target.display()));
+                    }
+                }
+            }
+        }
+
+        fs::remove_dir_all(&session)?;
+        Ok(())
+    }
+}
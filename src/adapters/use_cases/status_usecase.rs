@@ -0,0 +1,88 @@
+use super::tracked_files::{TrackedFile, TrackedFilesCollector};
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::store_context::StoreContext;
+use crate::adapters::executor::{same_content, Logger};
+use crate::application::error::VacuumError;
+use crate::application::executor;
+use crate::application::report::Report;
+use crate::application::usecase::UseCase;
+use crate::domain::App;
+use colored::*;
+use std::cell::Cell;
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq)]
+pub enum FileStatus {
+    Unchanged,
+    Modified,
+    MissingLive,
+    MissingInSnapshot,
+    New,
+}
+
+impl FileStatus {
+    fn of(file: &TrackedFile) -> Self {
+        match (file.live.is_file(), file.stored.is_file()) {
+            (true, true) if same_content(&file.live, &file.stored) => FileStatus::Unchanged,
+            (true, true) => FileStatus::Modified,
+            (false, _) => FileStatus::MissingLive,
+            (true, false) if file.from_pattern => FileStatus::New,
+            (true, false) => FileStatus::MissingInSnapshot,
+        }
+    }
+
+    fn label(&self) -> ColoredString {
+        match self {
+            FileStatus::Unchanged => "unchanged".normal(),
+            FileStatus::Modified => "modified".yellow(),
+            FileStatus::MissingLive => "missing live".red(),
+            FileStatus::MissingInSnapshot => "missing in snapshot".red(),
+            FileStatus::New => "new".green(),
+        }
+    }
+}
+
+/// Compares the files tracked by an app on the machine with the stored ones.
+pub struct StatusUseCase {
+    app_dir: PathBuf,
+    drifted: Cell<bool>,
+}
+
+impl StatusUseCase {
+    pub fn new(app_dir: PathBuf) -> Self {
+        Self {
+            app_dir,
+            drifted: Cell::new(false),
+        }
+    }
+
+    /// Whether any file of the last app that was run differs from the store.
+    pub fn has_drift(&self) -> bool {
+        self.drifted.get()
+    }
+}
+
+impl UseCase for StatusUseCase {
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
+        let collector = TrackedFilesCollector::new();
+        executor::execute(
+            &collector,
+            &StoreContext::new(self.app_dir.clone(), IgnoreFile::for_app(&self.app_dir)?),
+            app,
+        )?;
+
+        let logger = Logger::new(app.name.to_string());
+        for file in collector.into_files() {
+            let status = FileStatus::of(&file);
+            if status != FileStatus::Unchanged {
+                self.drifted.set(true);
+            }
+            let path = match status {
+                FileStatus::MissingLive => file.stored,
+                _ => file.live,
+            };
+            logger.print(format!("{:<19} {}", status.label(), path.display()));
+        }
+        Ok(Report::default())
+    }
+}
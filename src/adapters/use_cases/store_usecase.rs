@@ -1,24 +1,92 @@
+use super::manifest::{Manifest, MANIFEST_FILE};
+use super::prune::{not_holding_files, prune};
+use super::RunOptions;
+use crate::adapters::context::ignore_file::IgnoreFile;
 use crate::adapters::context::store_context::StoreContext;
-use crate::adapters::executor::FileSystemExecutor;
+use crate::adapters::executor::{
+    DryRunExecutor, FileSystemExecutor, Logger, Metadata, METADATA_FILE,
+};
 use crate::application::error::VacuumError;
 use crate::application::executor;
+use crate::application::report::Report;
 use crate::application::usecase::UseCase;
 use crate::domain::App;
+use colored::*;
+use std::collections::BTreeSet;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 pub struct StoreUseCase {
     app_dir: PathBuf,
+    options: RunOptions,
 }
 
 impl StoreUseCase {
-    pub fn new(app_dir: PathBuf) -> Self {
-        Self { app_dir }
+    pub fn new(app_dir: PathBuf, options: RunOptions) -> Self {
+        Self { app_dir, options }
+    }
+
+    /// Prunes the files this run did not write, unless some of them failed.
+    fn prune(
+        &self,
+        app: &App,
+        written: &BTreeSet<PathBuf>,
+        report: &Report,
+        metadata: Option<&Metadata>,
+    ) -> Result<Vec<PathBuf>, VacuumError> {
+        let logger = Logger::new(app.name.to_string());
+        let warn = |reason: String| {
+            logger.print(format!("{} not pruning {}", "Warning".yellow(), reason));
+            Ok(Vec::new())
+        };
+        if report.failed() > 0 {
+            return warn("because some files failed".into());
+        }
+        let mut keep = match not_holding_files(&self.app_dir, app) {
+            Ok(keep) => keep,
+            Err(e) => return warn(format!("files of other machines: {}", e)),
+        };
+        keep.extend(written.iter().cloned());
+
+        let pruned = prune(&self.app_dir, &keep, self.options.dry_run, &logger)?;
+        if let Some(metadata) = metadata {
+            metadata.retain(|stored| keep.contains(stored));
+        }
+        Ok(pruned)
     }
 }
 
 impl UseCase for StoreUseCase {
-    fn run(&self, app: &App) -> Result<(), VacuumError> {
-        let executor = FileSystemExecutor::new(app.name.to_string());
-        executor::execute(&executor, &StoreContext::new(self.app_dir.clone()), &app)
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
+        let ignore = IgnoreFile::for_app(&self.app_dir)?;
+        let ctx = StoreContext::new(self.app_dir.clone(), ignore);
+        if self.options.dry_run {
+            let executor = DryRunExecutor::new(app.name.to_string());
+            executor::execute(&executor, &ctx, app)?;
+            let written = executor.written();
+            let mut report = executor.into_report();
+            if self.options.prune {
+                report.removed += self.prune(app, &written, &report, None)?.len();
+            }
+            return Ok(report);
+        }
+        let metadata = Rc::new(Metadata::load(self.app_dir.clone())?);
+        let executor = FileSystemExecutor::new(app.name.to_string())
+            .with_metadata(metadata.clone())
+            .keep_going(self.options.keep_going);
+        executor::execute(&executor, &ctx, app)?;
+        let written = executor.written();
+        let mut report = executor.into_report();
+        if self.options.prune {
+            let pruned = self.prune(app, &written, &report, Some(&metadata))?;
+            report.removed += pruned.len();
+            report.touched.extend(pruned);
+        }
+        metadata.save()?;
+        Manifest::build(&self.app_dir, app)?.save(&self.app_dir)?;
+        report.touched.extend(written);
+        report.touched.push(self.app_dir.join(METADATA_FILE));
+        report.touched.push(self.app_dir.join(MANIFEST_FILE));
+        Ok(report)
     }
 }
@@ -0,0 +1,148 @@
+use crate::adapters::context::store_context::StoreContext;
+use crate::adapters::tree::{self, EntryKind};
+use crate::application::context::{Context, PairedContext};
+use crate::application::error::VacuumError;
+use crate::application::Handler;
+use crate::domain::{Command, DependencyCheck, Directory};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// A file tracked by an app definition, paired the same way `store` pairs it.
+pub struct TrackedFile {
+    pub live: PathBuf,
+    pub stored: PathBuf,
+    /// Whether the file was matched by a `files` pattern or found in a `dir`.
+    pub from_pattern: bool,
+    /// The action tracking the file, e.g. `files "*.json"`.
+    pub action: String,
+}
+
+/// A file written into the store folder with the output of a command.
+pub struct TrackedOutput {
+    pub stored: PathBuf,
+    /// The command writing the file, e.g. `exec "code --list-extensions"`.
+    pub action: String,
+}
+
+/// Collects the files tracked by an app, on the machine or in the store folder.
+pub struct TrackedFilesCollector {
+    files: RefCell<Vec<TrackedFile>>,
+    outputs: RefCell<Vec<TrackedOutput>>,
+}
+
+impl TrackedFilesCollector {
+    pub fn new() -> Self {
+        Self {
+            files: RefCell::new(Vec::new()),
+            outputs: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn into_files(self) -> Vec<TrackedFile> {
+        self.files.into_inner()
+    }
+
+    /// Returns the tracked files along with the command outputs.
+    pub fn into_parts(self) -> (Vec<TrackedFile>, Vec<TrackedOutput>) {
+        (self.files.into_inner(), self.outputs.into_inner())
+    }
+}
+
+impl Handler for TrackedFilesCollector {
+    type Context = StoreContext;
+
+    fn handle_file(
+        &self,
+        ctx: &Self::Context,
+        _: &Option<Vec<DependencyCheck>>,
+    ) -> Result<(), VacuumError> {
+        let (live, stored) = ctx.current();
+        if live.is_file() || stored.is_file() {
+            let name = live.file_name().unwrap_or_default().to_string_lossy();
+            let action = format!("file {:?}", name);
+            self.files.borrow_mut().push(TrackedFile {
+                live,
+                stored,
+                from_pattern: false,
+                action,
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_files<S: AsRef<str>>(
+        &self,
+        ctx: &Self::Context,
+        pattern: S,
+        exclude: &[String],
+    ) -> Result<(), VacuumError> {
+        let pattern = pattern.as_ref();
+        let mut pairs = BTreeSet::new();
+        for found in ctx.search(pattern, exclude) {
+            pairs.insert(found.current());
+        }
+
+        let (live_dir, stored_dir) = ctx.current();
+        for stored in stored_dir.search(pattern, exclude) {
+            if ctx.is_ignored(&stored, stored.is_dir()) {
+                continue;
+            }
+            let remaining = stored.strip_prefix(&stored_dir).unwrap();
+            pairs.insert((live_dir.join(remaining), stored));
+        }
+
+        let mut files = self.files.borrow_mut();
+        for (live, stored) in pairs {
+            if live.is_dir() || stored.is_dir() {
+                continue;
+            }
+            files.push(TrackedFile {
+                live,
+                stored,
+                from_pattern: true,
+                action: format!("files {:?}", pattern),
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_dir(&self, ctx: &Self::Context, dir: &Directory) -> Result<(), VacuumError> {
+        let (live_dir, stored_dir) = ctx.sub(&dir.name).current();
+        let mut paths = BTreeSet::new();
+        for root in [&live_dir, &stored_dir] {
+            for entry in tree::walk(root, dir.symlinks, &dir.exclude)? {
+                if entry.kind != EntryKind::Dir
+                    && !ctx.is_ignored(&stored_dir.join(&entry.path), false)
+                {
+                    paths.insert(entry.path);
+                }
+            }
+        }
+
+        let mut files = self.files.borrow_mut();
+        for path in paths {
+            let (live, stored) = (live_dir.join(&path), stored_dir.join(&path));
+            // dangling links have no content to compare
+            if live.is_file() || stored.is_file() {
+                files.push(TrackedFile {
+                    live,
+                    stored,
+                    from_pattern: true,
+                    action: format!("dir {:?}", dir.name),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_execute(&self, ctx: &Self::Context, command: &Command) -> Result<(), VacuumError> {
+        if let Some(file_name) = &command.output {
+            self.outputs.borrow_mut().push(TrackedOutput {
+                stored: ctx.stored().join(file_name),
+                action: format!("exec {:?}", command.command),
+            });
+        }
+        Ok(())
+    }
+}
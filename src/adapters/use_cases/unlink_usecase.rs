@@ -0,0 +1,45 @@
+use super::tracked_files::TrackedFilesCollector;
+use crate::adapters::context::ignore_file::IgnoreFile;
+use crate::adapters::context::store_context::StoreContext;
+use crate::adapters::executor::Logger;
+use crate::application::error::VacuumError;
+use crate::application::executor;
+use crate::application::report::Report;
+use crate::application::usecase::UseCase;
+use crate::domain::App;
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Removes the links `restore --link` created and nothing else.
+pub struct UnlinkUseCase {
+    app_dir: PathBuf,
+}
+
+impl UnlinkUseCase {
+    pub fn new(app_dir: PathBuf) -> Self {
+        Self { app_dir }
+    }
+}
+
+impl UseCase for UnlinkUseCase {
+    fn run(&self, app: &App) -> Result<Report, VacuumError> {
+        let collector = TrackedFilesCollector::new();
+        executor::execute(
+            &collector,
+            &StoreContext::new(self.app_dir.clone(), IgnoreFile::for_app(&self.app_dir)?),
+            app,
+        )?;
+
+        let logger = Logger::new(app.name.to_string());
+        let mut report = Report::default();
+        for file in collector.into_files() {
+            if fs::read_link(&file.live).is_ok_and(|target| target == file.stored) {
+                fs::remove_file(&file.live)?;
+                report.removed += 1;
+                logger.print(format!("{} {}", "Unlink".blue(), file.live.display()));
+            }
+        }
+        Ok(report)
+    }
+}
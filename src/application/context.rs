@@ -1,9 +1,36 @@
+use crate::domain::KnownFolder;
+use std::path::{Path, PathBuf};
+
 pub trait Context: Sized {
     type Current;
     fn current(&self) -> Self::Current;
-    fn home(&self) -> Self;
-    fn config(&self) -> Self;
-    fn local(&self) -> Self;
+    /// The folder of the platform, `None` when the platform has none.
+    fn known(&self, folder: KnownFolder) -> Option<Self>;
+    /// The absolute `path` on the machine.
+    fn root<S: AsRef<str>>(&self, path: S) -> Self;
     fn sub<S: AsRef<str>>(&self, sub: S) -> Self;
-    fn search(&self, pattern: &str) -> Vec<Self>;
+    /// Like `sub`, but the path in the store folder is named `stored`.
+    fn sub_as<S: AsRef<str>>(&self, live: S, stored: S) -> Self;
+    /// Finds the paths matching `pattern` except the ones matching `exclude`.
+    fn search(&self, pattern: &str, exclude: &[String]) -> Vec<Self>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// From the machine into the store folder.
+    Store,
+    /// From the store folder onto the machine.
+    Restore,
+}
+
+/// Pairs a path on the machine with its counterpart in the store folder.
+pub trait PairedContext: Context<Current = (PathBuf, PathBuf)> {
+    /// Which way files are copied between the two paths.
+    fn direction(&self) -> Direction;
+    /// The path on the machine.
+    fn live(&self) -> PathBuf;
+    /// The path in the store folder.
+    fn stored(&self) -> PathBuf;
+    /// Whether `stored`, a path in the store folder, is listed in its ignore file.
+    fn is_ignored(&self, stored: &Path, is_dir: bool) -> bool;
 }
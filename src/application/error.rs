@@ -1,17 +1,123 @@
 use std::error::Error;
 use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum VacuumError {
     IoError(std::io::Error),
-    ParseError(pom::Error),
+    ParseError(ParseError),
+    UnknownApp(String),
+    CommandNotFound(String),
+    InvalidCommand(String, String),
+    CommandFailed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    CommandTimedOut {
+        command: String,
+        timeout: Duration,
+    },
+    InvalidPattern(String, String),
+    UndefinedVariable(String, String),
+    /// An included file that does not exist and the file including it.
+    IncludeNotFound(String, String),
+    /// The files that include each other, starting and ending with the same.
+    IncludeCycle(Vec<String>),
+    /// A file restore would replace with a link.
+    WouldReplace(PathBuf),
+    /// A manifest file that cannot be read and why.
+    InvalidManifest(String, String),
+}
+
+/// Describes where and why a .vacuum file failed to parse.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    /// The line of the file the error is on.
+    pub source_line: String,
+    /// What was found at the error location, `None` at the end of the file.
+    pub found: Option<String>,
+    /// Descriptions of the tokens that would have been accepted instead.
+    pub expected: Vec<String>,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}:{}:{}: ", self.file, self.line, self.column)?;
+        match &self.found {
+            Some(found) => write!(f, "unexpected `{}`", found)?,
+            None => write!(f, "unexpected end of file")?,
+        }
+        match self.expected.as_slice() {
+            [] => {}
+            [expected] => write!(f, ", expected {}", expected)?,
+            expected => write!(f, ", expected one of {}", expected.join(", "))?,
+        }
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(f, "{} | {}^", gutter, " ".repeat(self.column - 1))
+    }
 }
 
 impl Display for VacuumError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
-            VacuumError::ParseError(e) => write!(f, "{:?}", e),
+            VacuumError::ParseError(e) => write!(f, "Parse Error: {}", e),
             VacuumError::IoError(e) => write!(f, "IO Error: {}", e),
+            VacuumError::UnknownApp(name) => write!(f, "Unknown app: {}", name),
+            VacuumError::CommandNotFound(command) => {
+                write!(f, "Command not found: {}", command)
+            }
+            VacuumError::InvalidCommand(command, reason) => {
+                write!(f, "Invalid command `{}`: {}", command, reason)
+            }
+            VacuumError::CommandFailed {
+                command,
+                status,
+                stderr,
+            } => {
+                write!(f, "Command `{}` failed with {}", command, status)?;
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+            VacuumError::CommandTimedOut { command, timeout } => write!(
+                f,
+                "Command `{}` timed out after {} seconds",
+                command,
+                timeout.as_secs()
+            ),
+            VacuumError::InvalidPattern(pattern, reason) => {
+                write!(f, "Invalid pattern `{}`: {}", pattern, reason)
+            }
+            VacuumError::UndefinedVariable(name, input) => {
+                write!(f, "Undefined variable `{}` in \"{}\"", name, input)
+            }
+            VacuumError::IncludeNotFound(file_name, included_from) => write!(
+                f,
+                "Included file {} not found, included from {}",
+                file_name, included_from
+            ),
+            VacuumError::IncludeCycle(files) => {
+                write!(f, "Include cycle: {}", files.join(" -> "))
+            }
+            VacuumError::WouldReplace(path) => write!(
+                f,
+                "Refusing to replace {} with a link, use --force to replace it",
+                path.display()
+            ),
+            VacuumError::InvalidManifest(path, reason) => {
+                write!(f, "Invalid manifest `{}`: {}", path, reason)
+            }
         }
     }
 }
@@ -1,38 +1,139 @@
 use crate::application::context::Context;
 use crate::application::error::VacuumError;
 use crate::application::handler::Handler;
-use crate::domain::{Action, App, Folder};
+use crate::application::variables::Variables;
+use crate::domain::{Action, App, Command, Condition, Directory, Folder, RestoreCommand};
+
+/// Evaluates a `when` condition against the machine vacuum runs on.
+fn holds(condition: &Condition) -> bool {
+    match condition {
+        Condition::Os(os) => os.eq_ignore_ascii_case(std::env::consts::OS),
+        Condition::Hostname(name) => gethostname::gethostname()
+            .to_str()
+            .is_some_and(|hostname| hostname.eq_ignore_ascii_case(name)),
+        Condition::Env(variable) => std::env::var_os(variable).is_some(),
+    }
+}
+
+/// Copies `command` with variables expanded in its strings.
+fn expand_command(variables: &Variables, command: &Command) -> Result<Command, VacuumError> {
+    let mut env = Vec::with_capacity(command.env.len());
+    for (key, value) in &command.env {
+        env.push((key.clone(), variables.expand(value)?));
+    }
+    let on_restore = match &command.on_restore {
+        Some(restore) => Some(RestoreCommand {
+            command: variables.expand(&restore.command)?,
+            mode: restore.mode,
+        }),
+        None => None,
+    };
+    Ok(Command {
+        command: variables.expand(&command.command)?,
+        mode: command.mode,
+        output: command
+            .output
+            .as_deref()
+            .map(|o| variables.expand(o))
+            .transpose()?,
+        optional: command.optional,
+        timeout: command.timeout,
+        env,
+        on_restore,
+    })
+}
+
+// patterns are checked by the parser, but variables are only known now
+fn expand_pattern(variables: &Variables, pattern: &str) -> Result<String, VacuumError> {
+    let pattern = variables.expand(pattern)?;
+    glob::Pattern::new(&pattern)
+        .map_err(|e| VacuumError::InvalidPattern(pattern.clone(), e.msg.to_owned()))?;
+    Ok(pattern)
+}
+
+fn expand_patterns(variables: &Variables, patterns: &[String]) -> Result<Vec<String>, VacuumError> {
+    patterns
+        .iter()
+        .map(|pattern| expand_pattern(variables, pattern))
+        .collect()
+}
+
+/// Which `when` branches the actions are handled in.
+#[derive(Clone, Copy, PartialEq)]
+enum Branches {
+    Holding,
+    NotHolding,
+    /// Every action of a branch that does not hold.
+    All,
+}
 
 fn handle_actions<C>(
     handler: &impl Handler<Context = C>,
     ctx: &C,
     actions: &[Action],
+    variables: &Variables,
+    branches: Branches,
 ) -> Result<(), VacuumError>
 where
     C: Context,
 {
+    let mut variables = variables.clone();
     for step in actions {
+        let skipped = branches == Branches::NotHolding;
         match step {
-            Action::File(filename, dependency_checks) => {
-                handler.handle_file(ctx, filename, dependency_checks)?
+            Action::Let(name, value) => variables.declare(name, value)?,
+            Action::Include(file_name) => {
+                unreachable!("{} is included when the app is loaded", file_name)
+            }
+            Action::File(..) | Action::Files(..) | Action::Dir(_) | Action::Execute(_)
+                if skipped => {}
+            Action::File(filename, stored_as, dependency_checks) => {
+                let filename = variables.expand(filename)?;
+                let sub_context = match stored_as {
+                    Some(stored_as) => ctx.sub_as(filename, variables.expand(stored_as)?),
+                    None => ctx.sub(filename),
+                };
+                handler.handle_file(&sub_context, dependency_checks)?
+            }
+            Action::Files(pattern, exclude) => handler.handle_files(
+                ctx,
+                expand_pattern(&variables, pattern)?,
+                &expand_patterns(&variables, exclude)?,
+            )?,
+            Action::Dir(dir) => {
+                let dir = Directory {
+                    name: variables.expand(&dir.name)?,
+                    symlinks: dir.symlinks,
+                    exclude: expand_patterns(&variables, &dir.exclude)?,
+                };
+                handler.handle_dir(ctx, &dir)?
             }
-            Action::Files(pattern) => handler.handle_files(ctx, pattern)?,
             Action::Context(context, sub_actions) => {
                 let mut sub_contexts = Vec::new();
                 match context {
-                    Folder::Home => sub_contexts.push(ctx.home()),
-                    Folder::Config => sub_contexts.push(ctx.config()),
-                    Folder::Local => sub_contexts.push(ctx.local()),
-                    Folder::Custom(name) => sub_contexts.push(ctx.sub(name)),
-                    Folder::Search(pattern) => sub_contexts.extend(ctx.search(pattern)),
+                    Folder::Known(folder) => sub_contexts.extend(ctx.known(*folder)),
+                    Folder::Root(path) => sub_contexts.push(ctx.root(variables.expand(path)?)),
+                    Folder::Custom(name) => sub_contexts.push(ctx.sub(variables.expand(name)?)),
+                    Folder::Search(pattern, exclude) => sub_contexts.extend(ctx.search(
+                        &expand_pattern(&variables, pattern)?,
+                        &expand_patterns(&variables, exclude)?,
+                    )),
                 }
 
                 for sub_context in sub_contexts {
-                    handle_actions(handler, &sub_context, &sub_actions)?;
+                    handle_actions(handler, &sub_context, sub_actions, &variables, branches)?;
                 }
             }
-            Action::Execute(command, file_name) => {
-                handler.handle_execute(ctx, command, file_name)?
+            Action::When(condition, sub_actions) => {
+                let branches = match branches {
+                    Branches::Holding if !holds(condition) => continue,
+                    Branches::NotHolding if !holds(condition) => Branches::All,
+                    branches => branches,
+                };
+                handle_actions(handler, ctx, sub_actions, &variables, branches)?;
+            }
+            Action::Execute(command) => {
+                handler.handle_execute(ctx, &expand_command(&variables, command)?)?
             }
         }
     }
@@ -47,5 +148,108 @@ pub fn execute<C>(
 where
     C: Context,
 {
-    handle_actions(handler, ctx, &app.actions)
+    handle_actions(
+        handler,
+        ctx,
+        &app.actions,
+        &Variables::default(),
+        Branches::Holding,
+    )
+}
+
+/// Handles only the actions of `when` branches that do not hold on this machine.
+pub fn execute_not_holding<C>(
+    handler: &impl Handler<Context = C>,
+    ctx: &C,
+    app: &App,
+) -> Result<(), VacuumError>
+where
+    C: Context,
+{
+    handle_actions(
+        handler,
+        ctx,
+        &app.actions,
+        &Variables::default(),
+        Branches::NotHolding,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DependencyCheck;
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+
+    #[derive(Default)]
+    struct FileNames(RefCell<Vec<String>>);
+
+    impl Handler for FileNames {
+        type Context = PathBuf;
+
+        fn handle_file(
+            &self,
+            ctx: &PathBuf,
+            _: &Option<Vec<DependencyCheck>>,
+        ) -> Result<(), VacuumError> {
+            self.0.borrow_mut().push(ctx.display().to_string());
+            Ok(())
+        }
+
+        fn handle_files<S: AsRef<str>>(
+            &self,
+            _: &PathBuf,
+            _: S,
+            _: &[String],
+        ) -> Result<(), VacuumError> {
+            Ok(())
+        }
+
+        fn handle_dir(&self, _: &PathBuf, _: &Directory) -> Result<(), VacuumError> {
+            Ok(())
+        }
+
+        fn handle_execute(&self, _: &PathBuf, _: &Command) -> Result<(), VacuumError> {
+            Ok(())
+        }
+    }
+
+    fn file(name: &str) -> Action {
+        Action::File(name.into(), None, None)
+    }
+
+    fn when(variable: &str, actions: Vec<Action>) -> Action {
+        Action::When(Condition::Env(variable.into()), actions)
+    }
+
+    #[test]
+    fn test_execute_branches() {
+        let app = App {
+            name: "app".into(),
+            actions: vec![
+                file("always"),
+                when(
+                    "PATH",
+                    vec![
+                        file("holds"),
+                        when("VACUUM_TEST_UNSET", vec![file("nested")]),
+                    ],
+                ),
+                when(
+                    "VACUUM_TEST_UNSET",
+                    vec![file("other"), when("PATH", vec![file("inner")])],
+                ),
+            ],
+            dependencies: None,
+        };
+
+        let holding = FileNames::default();
+        execute(&holding, &PathBuf::new(), &app).unwrap();
+        assert_eq!(holding.0.into_inner(), vec!["always", "holds"]);
+
+        let not_holding = FileNames::default();
+        execute_not_holding(&not_holding, &PathBuf::new(), &app).unwrap();
+        assert_eq!(not_holding.0.into_inner(), vec!["nested", "other", "inner"]);
+    }
 }
@@ -1,24 +1,21 @@
 use crate::application::context::Context;
 use crate::application::error::VacuumError;
-use crate::domain::DependencyCheck;
+use crate::domain::{Command, DependencyCheck, Directory};
 
 pub trait Handler {
     type Context: Context;
-    fn handle_file<S: AsRef<str>>(
+    /// Handles a single file, `ctx` points at the file itself.
+    fn handle_file(
         &self,
         ctx: &Self::Context,
-        file_name: S,
         dependency_checks: &Option<Vec<DependencyCheck>>,
     ) -> Result<(), VacuumError>;
     fn handle_files<S: AsRef<str>>(
         &self,
         ctx: &Self::Context,
         pattern: S,
+        exclude: &[String],
     ) -> Result<(), VacuumError>;
-    fn handle_execute<S: AsRef<str>>(
-        &self,
-        ctx: &Self::Context,
-        command: S,
-        file_name: &Option<String>,
-    ) -> Result<(), VacuumError>;
+    fn handle_dir(&self, ctx: &Self::Context, dir: &Directory) -> Result<(), VacuumError>;
+    fn handle_execute(&self, ctx: &Self::Context, command: &Command) -> Result<(), VacuumError>;
 }
@@ -3,6 +3,8 @@ pub mod error;
 pub mod executor;
 pub mod handler;
 pub mod parser;
+pub mod report;
 pub mod usecase;
+pub mod variables;
 
 pub use handler::Handler;
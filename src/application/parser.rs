@@ -1,6 +1,9 @@
 use crate::application::error::VacuumError;
-use crate::domain::App;
+use crate::domain::{Action, App};
 
 pub trait VacuumFileParser {
-    fn parse(input: String) -> Result<App, VacuumError>;
+    /// Parses the apps defined in a .vacuum file, `file_name` is only used in errors.
+    fn parse(file_name: &str, input: String) -> Result<Vec<App>, VacuumError>;
+    /// Parses the actions of a fragment included by apps.
+    fn parse_fragment(file_name: &str, input: String) -> Result<Vec<Action>, VacuumError>;
 }
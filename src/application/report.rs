@@ -0,0 +1,21 @@
+use crate::application::error::VacuumError;
+use std::path::PathBuf;
+
+/// What happened while running a use case for an app.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub copied: usize,
+    pub removed: usize,
+    pub skipped: usize,
+    pub commands: usize,
+    /// What failed and why, only collected when running in keep going mode.
+    pub failures: Vec<(String, VacuumError)>,
+    /// Files written or removed in the store folder, only collected by store.
+    pub touched: Vec<PathBuf>,
+}
+
+impl Report {
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+}
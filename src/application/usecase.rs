@@ -1,6 +1,7 @@
 use crate::application::error::VacuumError;
+use crate::application::report::Report;
 use crate::domain::App;
 
 pub trait UseCase {
-    fn run(&self, app: &App) -> Result<(), VacuumError>;
+    fn run(&self, app: &App) -> Result<Report, VacuumError>;
 }
@@ -0,0 +1,137 @@
+use crate::application::error::VacuumError;
+use std::collections::HashMap;
+
+/// Variables declared with `let` that are visible to the actions of a block.
+#[derive(Clone, Default)]
+pub struct Variables {
+    values: HashMap<String, String>,
+}
+
+impl Variables {
+    /// Expands `value` and makes it available as `${name}`.
+    pub fn declare(&mut self, name: &str, value: &str) -> Result<(), VacuumError> {
+        let value = self.expand(value)?;
+        self.values.insert(name.to_owned(), value);
+        Ok(())
+    }
+
+    /// Expands `${name}`, `${env:VAR}` and a leading `~`, `$${` is kept as a literal `${`.
+    pub fn expand(&self, input: &str) -> Result<String, VacuumError> {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+        if rest == "~" || rest.starts_with("~/") {
+            let home = dirs_next::home_dir().unwrap_or_default();
+            output.push_str(&home.to_string_lossy());
+            rest = &rest[1..];
+        }
+
+        while let Some(start) = rest.find('$') {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if rest.starts_with("$${") {
+                output.push_str("${");
+                rest = &rest[3..];
+                continue;
+            }
+            if !rest.starts_with("${") {
+                output.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+            let name = &rest[2..end];
+            let value = match name.strip_prefix("env:") {
+                Some(variable) => std::env::var(variable).ok(),
+                None => self.values.get(name).cloned(),
+            };
+            match value {
+                Some(value) => output.push_str(&value),
+                None => {
+                    return Err(VacuumError::UndefinedVariable(
+                        name.to_owned(),
+                        input.to_owned(),
+                    ))
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs_next::home_dir()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_expand_variables() {
+        let mut variables = Variables::default();
+        variables.declare("name", "nvim").unwrap();
+        variables.declare("dir", "${name}/lua").unwrap();
+
+        assert_eq!(
+            variables.expand("${dir}/init.lua").unwrap(),
+            "nvim/lua/init.lua"
+        );
+        assert_eq!(variables.expand("${name}-${name}").unwrap(), "nvim-nvim");
+    }
+
+    #[test]
+    fn test_expand_leading_tilde() {
+        let variables = Variables::default();
+
+        assert_eq!(variables.expand("~").unwrap(), home());
+        assert_eq!(
+            variables.expand("~/.vimrc").unwrap(),
+            format!("{}/.vimrc", home())
+        );
+        assert_eq!(variables.expand("a/~/b").unwrap(), "a/~/b");
+        assert_eq!(variables.expand("~user").unwrap(), "~user");
+    }
+
+    #[test]
+    fn test_expand_escaped_and_unterminated() {
+        let variables = Variables::default();
+
+        assert_eq!(variables.expand("$${name}").unwrap(), "${name}");
+        assert_eq!(variables.expand("cost $5").unwrap(), "cost $5");
+        assert_eq!(variables.expand("a ${name").unwrap(), "a ${name");
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("VACUUM_TEST_EXPAND", "value");
+        let variables = Variables::default();
+
+        assert_eq!(
+            variables.expand("${env:VACUUM_TEST_EXPAND}").unwrap(),
+            "value"
+        );
+        assert!(matches!(
+            variables.expand("${env:VACUUM_TEST_UNDEFINED}"),
+            Err(VacuumError::UndefinedVariable(name, _)) if name == "env:VACUUM_TEST_UNDEFINED"
+        ));
+    }
+
+    #[test]
+    fn test_expand_undefined() {
+        let result = Variables::default().expand("x/${missing}");
+
+        assert!(matches!(
+            result,
+            Err(VacuumError::UndefinedVariable(name, input)) if name == "missing" && input == "x/${missing}"
+        ));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::application::error::VacuumError;
+use crate::domain::App;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "vacuum",
+    version,
+    about = "System-wide configuration file collector"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Store configuration files into folder
+    Store {
+        #[command(flatten)]
+        target: Target,
+        /// Only report what would be copied, created and executed
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove the stored files this run did not store
+        #[arg(long)]
+        prune: bool,
+        /// Commit the stored files when folder is in a git repository
+        #[arg(long, conflicts_with = "dry_run")]
+        commit: bool,
+    },
+    /// Restore configuration files from folder
+    Restore {
+        #[command(flatten)]
+        target: Target,
+        /// Only report what would be copied, created and executed
+        #[arg(long)]
+        dry_run: bool,
+        /// Link the configuration files to the ones in folder instead of copying them
+        #[arg(long)]
+        link: bool,
+        /// Replace existing configuration files with links
+        #[arg(long, requires = "link")]
+        force: bool,
+    },
+    /// Remove the links to folder created by restore --link
+    Unlink {
+        #[command(flatten)]
+        target: Target,
+    },
+    /// List possible dependencies based on configuration files
+    Deps {
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Compare configuration files with the ones stored in folder
+    Status {
+        #[command(flatten)]
+        target: Target,
+    },
+    /// Show differences between configuration files and the ones in folder
+    Diff {
+        #[command(flatten)]
+        target: Target,
+        /// Only show the differences of this app
+        app: Option<String>,
+    },
+    /// Put back the files replaced by the last restore
+    Rollback,
+    /// Manage the app definitions bundled with vacuum
+    Apps {
+        #[command(subcommand)]
+        command: AppsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AppsCommand {
+    /// Write the bundled app definitions into a directory for customization
+    Export {
+        /// Directory to write the .vacuum files into
+        dir: PathBuf,
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Args)]
+pub struct Target {
+    /// Folder the configuration files are stored in
+    #[arg(default_value = "output")]
+    pub folder: PathBuf,
+    #[command(flatten)]
+    pub selection: Selection,
+    /// Carry on with the remaining files and apps when one of them fails
+    #[arg(long)]
+    pub keep_going: bool,
+}
+
+#[derive(Args)]
+pub struct Selection {
+    /// Only process the app with this name, can be repeated
+    #[arg(long = "app", value_name = "NAME")]
+    pub apps: Vec<String>,
+    /// Skip the app with this name, can be repeated
+    #[arg(long = "exclude-app", value_name = "NAME")]
+    pub excluded_apps: Vec<String>,
+    /// Additional directory to read .vacuum files from, taking precedence over the others
+    #[arg(long, value_name = "PATH")]
+    pub apps_dir: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn parse_args() -> Self {
+        let mut cli = Self::parse();
+        if let Command::Diff {
+            target,
+            app: Some(app),
+        } = &mut cli.command
+        {
+            target.selection.apps.push(app.clone());
+        }
+        cli
+    }
+}
+
+impl Command {
+    /// The folder and app selection of commands that run against apps.
+    pub fn target(&self) -> Option<&Target> {
+        match self {
+            Command::Store { target, .. }
+            | Command::Restore { target, .. }
+            | Command::Unlink { target }
+            | Command::Deps { target }
+            | Command::Status { target }
+            | Command::Diff { target, .. } => Some(target),
+            Command::Rollback | Command::Apps { .. } => None,
+        }
+    }
+}
+
+impl Selection {
+    /// Filters `apps` down to the selected ones, ignoring case.
+    pub fn select(&self, apps: Vec<App>) -> Result<Vec<App>, VacuumError> {
+        let matches = |names: &[String], app: &App| {
+            names.iter().any(|name| app.name.eq_ignore_ascii_case(name))
+        };
+
+        for name in self.apps.iter().chain(&self.excluded_apps) {
+            if !apps.iter().any(|app| app.name.eq_ignore_ascii_case(name)) {
+                return Err(VacuumError::UnknownApp(name.clone()));
+            }
+        }
+
+        Ok(apps
+            .into_iter()
+            .filter(|app| self.apps.is_empty() || matches(&self.apps, app))
+            .filter(|app| !matches(&self.excluded_apps, app))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apps(names: &[&str]) -> Vec<App> {
+        names
+            .iter()
+            .map(|name| App {
+                name: name.to_string(),
+                actions: vec![],
+                dependencies: None,
+            })
+            .collect()
+    }
+
+    fn selection(apps: &[&str], excluded_apps: &[&str]) -> Selection {
+        Selection {
+            apps: apps.iter().map(|name| name.to_string()).collect(),
+            excluded_apps: excluded_apps.iter().map(|name| name.to_string()).collect(),
+            apps_dir: None,
+        }
+    }
+
+    fn names(apps: Vec<App>) -> Vec<String> {
+        apps.into_iter().map(|app| app.name).collect()
+    }
+
+    #[test]
+    fn test_select_all_apps_by_default() {
+        let selected = selection(&[], &[]).select(apps(&["nvim", "fish"])).unwrap();
+
+        assert_eq!(names(selected), vec!["nvim", "fish"]);
+    }
+
+    #[test]
+    fn test_select_apps_case_insensitively() {
+        let selected = selection(&["NVim"], &[])
+            .select(apps(&["nvim", "fish"]))
+            .unwrap();
+
+        assert_eq!(names(selected), vec!["nvim"]);
+    }
+
+    #[test]
+    fn test_select_excluded_apps() {
+        let selected = selection(&[], &["fish"])
+            .select(apps(&["nvim", "fish", "git"]))
+            .unwrap();
+
+        assert_eq!(names(selected), vec!["nvim", "git"]);
+    }
+
+    #[test]
+    fn test_select_unknown_app() {
+        let result = selection(&["emacs"], &[]).select(apps(&["nvim"]));
+
+        assert!(matches!(result, Err(VacuumError::UnknownApp(name)) if name == "emacs"));
+    }
+
+    #[test]
+    fn test_select_unknown_excluded_app() {
+        let result = selection(&[], &["emacs"]).select(apps(&["nvim"]));
+
+        assert!(matches!(result, Err(VacuumError::UnknownApp(name)) if name == "emacs"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq)]
 pub struct App {
     pub name: String,
@@ -7,11 +9,71 @@ pub struct App {
 
 #[derive(Debug, PartialEq)]
 pub enum Folder {
+    Known(KnownFolder),
+    /// An absolute path, declared with `root`.
+    Root(String),
+    Custom(String),
+    /// Every directory matching the pattern, except the excluded ones.
+    Search(String, Vec<String>),
+}
+
+/// A folder of the platform, stored under its name in the app folder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KnownFolder {
     Home,
     Config,
     Local,
-    Custom(String),
-    Search(String),
+    Data,
+    Cache,
+    State,
+    Runtime,
+    Bin,
+    Fonts,
+    Desktop,
+    Documents,
+}
+
+impl KnownFolder {
+    pub const ALL: [KnownFolder; 11] = [
+        KnownFolder::Home,
+        KnownFolder::Config,
+        KnownFolder::Local,
+        KnownFolder::Data,
+        KnownFolder::Cache,
+        KnownFolder::State,
+        KnownFolder::Runtime,
+        KnownFolder::Bin,
+        KnownFolder::Fonts,
+        KnownFolder::Desktop,
+        KnownFolder::Documents,
+    ];
+
+    /// The keyword of the folder, also the name it is stored under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            KnownFolder::Home => "home",
+            KnownFolder::Config => "config",
+            KnownFolder::Local => "local",
+            KnownFolder::Data => "data",
+            KnownFolder::Cache => "cache",
+            KnownFolder::State => "state",
+            KnownFolder::Runtime => "runtime",
+            KnownFolder::Bin => "bin",
+            KnownFolder::Fonts => "fonts",
+            KnownFolder::Desktop => "desktop",
+            KnownFolder::Documents => "documents",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Condition {
+    /// The operating system is `linux`, `macos` or `windows`.
+    Os(String),
+    /// The name of the machine.
+    Hostname(String),
+    /// The environment variable is set.
+    Env(String),
 }
 
 #[derive(Debug, PartialEq)]
@@ -22,10 +84,62 @@ pub enum DependencyCheck {
 
 #[derive(Debug, PartialEq)]
 pub enum Action {
-    File(String, Option<Vec<DependencyCheck>>),
-    Files(String),
+    /// A file, the name it is stored under and the dependency checks run against it.
+    File(String, Option<String>, Option<Vec<DependencyCheck>>),
+    /// Files matching the pattern, except the excluded ones.
+    Files(String, Vec<String>),
+    Dir(Directory),
     Context(Folder, Vec<Action>),
-    Execute(String, Option<String>),
+    /// The actions of another file, replaced by them when the app is loaded.
+    Include(String),
+    /// Declares a variable for the following actions of the app.
+    Let(String, String),
+    /// Actions that only apply when the condition holds.
+    When(Condition, Vec<Action>),
+    Execute(Command),
+}
+
+/// A directory stored and restored recursively, including empty directories.
+#[derive(Debug, PartialEq)]
+pub struct Directory {
+    pub name: String,
+    pub symlinks: SymlinkPolicy,
+    /// Glob patterns matched against the relative path and the name of each entry.
+    pub exclude: Vec<String>,
+}
+
+/// How symbolic links inside a `dir` are handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Recreate the link itself, pointing at the same path.
+    Preserve,
+    /// Copy whatever the link points at.
+    Follow,
+    /// Leave links out.
+    Skip,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Command {
+    pub command: String,
+    pub mode: CommandMode,
+    /// File the standard output of the command is written into.
+    pub output: Option<String>,
+    /// Skip the command with a warning when its program is not installed.
+    pub optional: bool,
+    /// Seconds the command may run for before it is killed.
+    pub timeout: Option<u64>,
+    /// Environment variables set for the command.
+    pub env: Vec<(String, String)>,
+    /// Reapplies the captured output during restore.
+    pub on_restore: Option<RestoreCommand>,
+}
+
+/// A command run during restore instead of the command it belongs to.
+#[derive(Debug, PartialEq)]
+pub struct RestoreCommand {
+    pub command: String,
+    pub mode: CommandMode,
 }
 
 #[derive(Debug, PartialEq)]
@@ -33,3 +147,20 @@ pub struct Dependency {
     pub name: String,
     pub block: String,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandMode {
+    /// Split with shell quoting rules and run directly, declared with `execute`.
+    Args,
+    /// Run through the platform shell, declared with `shell`.
+    Shell,
+}
+
+impl fmt::Display for CommandMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandMode::Args => write!(f, "argv"),
+            CommandMode::Shell => write!(f, "shell"),
+        }
+    }
+}
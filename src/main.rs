@@ -1,71 +1,264 @@
 mod adapters;
 mod application;
+mod cli;
 mod domain;
 
 use crate::adapters::{
-    parsers::pom_parser::PomParser,
-    use_cases::{DepsUseCase, RestoreUseCase, StoreUseCase},
+    embedded,
+    executor::Journal,
+    git,
+    loader::DefinitionLoader,
+    use_cases::{
+        DepsUseCase, DiffUseCase, RestoreUseCase, RollbackUseCase, RunOptions, StatusUseCase,
+        StoreUseCase, UnlinkUseCase,
+    },
 };
 use crate::application::error::VacuumError;
-use crate::application::parser::VacuumFileParser;
+use crate::application::report::Report;
 use crate::application::usecase::UseCase;
+use crate::cli::{AppsCommand, Cli, Command};
 use crate::domain::App;
-use std::fs;
+use colored::*;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-
-fn parse_vacuum_files() -> Result<Vec<App>, VacuumError> {
-    let mut apps = Vec::new();
-    let dir = std::fs::read_dir("./apps/")?.filter_map(Result::ok);
-    for entry in dir {
-        if !entry.file_type()?.is_file() {
-            continue;
+fn parse_vacuum_files(apps_dir: Option<&Path>) -> Result<Vec<App>, VacuumError> {
+    if let Some(apps_dir) = apps_dir {
+        if !apps_dir.is_dir() {
+            return Err(VacuumError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("apps directory {} not found", apps_dir.display()),
+            )));
         }
-
-        let content = fs::read_to_string(entry.path())?;
-        let app = PomParser::parse(content)?;
-        apps.push(app);
     }
-    Ok(apps)
+    let search_path = DefinitionLoader::search_path(apps_dir.map(Path::to_path_buf));
+    DefinitionLoader::new(search_path).load()
 }
 
-fn main() -> Result<(), VacuumError> {
-    let mut args = std::env::args();
-    if args.len() < 2 {
-        println!("vacuum {}", VERSION);
-        println!("Usage: vacuum [command] <folder>");
-        println!(" commands:");
-        println!(" store   : Store configurations files into folder");
-        println!(" restore : Restore configurations files from folder");
-        println!(" deps    : List possible dependencies based on configuration files");
-        return Ok(());
-    }
+/// Runs the command and returns the exit code of the process.
+fn run(command: &Command) -> Result<i32, VacuumError> {
+    let target = match command {
+        Command::Rollback => {
+            RollbackUseCase::new(Journal::default_root()).run()?;
+            return Ok(0);
+        }
+        Command::Apps {
+            command: AppsCommand::Export { dir, force },
+        } => {
+            for path in embedded::export(dir, *force)? {
+                println!("{} {}", "Export".blue(), path.display());
+            }
+            return Ok(0);
+        }
+        _ => command.target().unwrap(),
+    };
 
-    let _ = args.next();
-    let command = args.next().unwrap_or_else(|| "store".to_owned());
-    let output_folder = args.next().unwrap_or_else(|| "output".to_owned());
     let current_dir = std::env::current_dir()?;
+    let journal = Rc::new(Journal::new(Journal::default_root()));
+    let mut drifted = false;
+    let mut reports = Vec::new();
 
-    let apps = parse_vacuum_files()?;
-    for app in apps {
+    let apps = parse_vacuum_files(target.selection.apps_dir.as_deref())?;
+    for app in target.selection.select(apps)? {
         let mut app_dir = current_dir.clone();
-        app_dir.push(output_folder.clone());
+        app_dir.push(&target.folder);
         app_dir.push(&app.name);
 
-        match command.as_ref() {
-            "store" => StoreUseCase::new(app_dir).run(&app)?,
-            "restore" => RestoreUseCase::new(app_dir).run(&app)?,
-            "deps" => DepsUseCase::new(app_dir).run(&app)?,
-            c => panic!("unknown command {}", c),
+        let options = |dry_run: bool| RunOptions {
+            dry_run,
+            keep_going: target.keep_going,
+            ..RunOptions::default()
+        };
+        let result = match command {
+            Command::Store { dry_run, prune, .. } => {
+                let options = RunOptions {
+                    prune: *prune,
+                    ..options(*dry_run)
+                };
+                StoreUseCase::new(app_dir, options).run(&app)
+            }
+            Command::Restore {
+                dry_run,
+                link,
+                force,
+                ..
+            } => {
+                let options = RunOptions {
+                    link: *link,
+                    force: *force,
+                    ..options(*dry_run)
+                };
+                RestoreUseCase::new(app_dir, options, journal.clone()).run(&app)
+            }
+            Command::Unlink { .. } => UnlinkUseCase::new(app_dir).run(&app),
+            Command::Deps { .. } => DepsUseCase::new(app_dir).run(&app),
+            Command::Diff { .. } => DiffUseCase::new(app_dir).run(&app),
+            Command::Status { .. } => {
+                let status = StatusUseCase::new(app_dir);
+                let result = status.run(&app);
+                drifted |= status.has_drift();
+                result
+            }
+            Command::Rollback | Command::Apps { .. } => unreachable!(),
         };
+
+        match result {
+            Ok(report) => reports.push((app.name, report)),
+            Err(e) if target.keep_going => {
+                eprintln!("{} {}: {}", "error:".red(), app.name, e);
+                let mut report = Report::default();
+                report.failures.push((app.name.clone(), e));
+                reports.push((app.name, report));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if let Command::Store { .. } | Command::Restore { .. } = command {
+        print_summary(&reports);
+    }
+    print_failures(&reports);
+    if let Command::Store { commit: true, .. } = command {
+        commit_store(&current_dir.join(&target.folder), &reports)?;
+    }
+
+    Ok(if reports.iter().any(|(_, report)| report.failed() > 0) {
+        2
+    } else if drifted {
+        1
+    } else {
+        0
+    })
+}
+
+fn print_summary(reports: &[(String, Report)]) {
+    let width = reports
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(Some(12))
+        .max()
+        .unwrap_or_default();
+    let row = |name: &str, columns: [String; 4]| {
+        format!(
+            "{:<width$} {:>8} {:>8} {:>8} {:>8}",
+            name,
+            columns[0],
+            columns[1],
+            columns[2],
+            columns[3],
+            width = width
+        )
+    };
+    let counts = |report: &Report| {
+        [
+            report.copied.to_string(),
+            report.skipped.to_string(),
+            report.failed().to_string(),
+            report.commands.to_string(),
+        ]
+    };
+
+    println!();
+    let header = ["Copied", "Skipped", "Failed", "Commands"].map(String::from);
+    println!("{}", row("App", header).bold());
+    let mut total = Report::default();
+    for (name, report) in reports {
+        let line = row(name, counts(report));
+        if report.failed() > 0 {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line);
+        }
+        total.copied += report.copied;
+        total.skipped += report.skipped;
+        total.commands += report.commands;
+    }
+    let mut columns = counts(&total);
+    columns[2] = reports
+        .iter()
+        .map(|(_, report)| report.failed())
+        .sum::<usize>()
+        .to_string();
+    let apps = format!("{} apps", reports.len());
+    println!("{}", row(&apps, columns).bold());
+}
+
+/// Commits the files store touched when `folder` is in a git repository and nothing failed.
+fn commit_store(folder: &Path, reports: &[(String, Report)]) -> Result<(), VacuumError> {
+    if reports.iter().any(|(_, report)| report.failed() > 0) {
+        eprintln!(
+            "{} store had failures, nothing was committed",
+            "warning:".yellow()
+        );
+        return Ok(());
+    }
+    let root = match git::repository_root(folder)? {
+        Some(root) => root,
+        None => {
+            eprintln!(
+                "{} {} is not in a git repository, nothing was committed",
+                "warning:".yellow(),
+                folder.display()
+            );
+            return Ok(());
+        }
+    };
+
+    let touched = reports
+        .iter()
+        .map(|(name, report)| (name.clone(), report.touched.clone()))
+        .collect::<Vec<_>>();
+    println!();
+    match git::commit(&root, &touched)? {
+        Some(message) => println!("{} {}", "Commit".blue(), message.lines().next().unwrap()),
+        None => println!("Nothing to commit"),
     }
     Ok(())
 }
 
+fn print_failures(reports: &[(String, Report)]) {
+    let failures = reports
+        .iter()
+        .flat_map(|(name, report)| report.failures.iter().map(move |f| (name, f)))
+        .collect::<Vec<_>>();
+    if failures.is_empty() {
+        return;
+    }
+
+    eprintln!();
+    eprintln!("{}", "Failures:".red().bold());
+    for (name, (what, e)) in failures {
+        eprintln!("[{:<10}] {}: {}", name.green(), what, e);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse_args();
+    match run(&cli.command) {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("{} {}", "error:".red(), e);
+            std::process::exit(2);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::adapters::loader::{DefinitionLoader, Source};
+
     #[test]
     fn all_vacuum_files_parsed_without_errors() {
-        assert!(super::parse_vacuum_files().is_ok());
+        let loader = DefinitionLoader::new(vec![Source::Dir("./apps".into())]);
+        assert!(loader.load().is_ok());
+    }
+
+    #[test]
+    fn all_embedded_vacuum_files_parsed_without_errors() {
+        let apps = DefinitionLoader::new(vec![Source::Embedded])
+            .load()
+            .unwrap();
+        assert!(!apps.is_empty());
     }
 }